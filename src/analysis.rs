@@ -0,0 +1,486 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::Duration;
+
+use crate::chessbot::{reporter::SilentReporter, GiffiBot, MATE, MATE_THRESHOLD};
+use crate::pgn::{self, PgnGame};
+use bitschess::prelude::*;
+
+// Centipawn loss needed for each judgement (inclusive)
+const INACCURACY_THRESHOLD: i32 = 50;
+const MISTAKE_THRESHOLD: i32 = 100;
+const BLUNDER_THRESHOLD: i32 = 300;
+/// Scores are capped before calculating the loss, so a single missed mate doesn't dominate the average.
+const LOSS_SCORE_CAP: i32 = 1000;
+/// Max moves of the engine's line written as a variation.
+const VARIATION_LENGTH: usize = 8;
+const PGN_LINE_WIDTH: usize = 79;
+
+const DEFAULT_DEPTH: i32 = 6;
+
+#[derive(Debug, Clone, Copy)]
+pub enum AnalysisLimit {
+    Depth(i32),
+    MoveTime(Duration),
+}
+
+#[derive(Debug)]
+pub enum AnalysisError {
+    Io(std::io::Error),
+    InvalidArgument(String),
+    InvalidFen(String),
+    /// `ply` is 1 based.
+    IllegalMove {
+        ply: usize,
+        san: String,
+    },
+}
+
+impl From<std::io::Error> for AnalysisError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Judgement {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Judgement {
+    pub const fn from_loss(centipawn_loss: i32) -> Option<Self> {
+        if centipawn_loss >= BLUNDER_THRESHOLD {
+            Some(Self::Blunder)
+        } else if centipawn_loss >= MISTAKE_THRESHOLD {
+            Some(Self::Mistake)
+        } else if centipawn_loss >= INACCURACY_THRESHOLD {
+            Some(Self::Inaccuracy)
+        } else {
+            None
+        }
+    }
+
+    /// Numeric Annotation Glyph: ?!, ? and ??
+    pub const fn nag(self) -> &'static str {
+        match self {
+            Self::Inaccuracy => "$6",
+            Self::Mistake => "$2",
+            Self::Blunder => "$4",
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Inaccuracy => "Inaccuracy",
+            Self::Mistake => "Mistake",
+            Self::Blunder => "Blunder",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AnnotatedMove {
+    pub san: String,
+    pub color: PieceColor,
+    pub fullmove: u32,
+    pub centipawn_loss: i32,
+    pub judgement: Option<Judgement>,
+    /// Engine's best line in SAN, empty if the played move was the engine's choice.
+    pub best_line: Vec<String>,
+    /// Evaluation before the move, from white's perspective.
+    pub best_score: i32,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlayerSummary {
+    pub moves: u32,
+    pub total_loss: i64,
+    pub inaccuracies: u32,
+    pub mistakes: u32,
+    pub blunders: u32,
+}
+
+impl PlayerSummary {
+    pub fn average_centipawn_loss(&self) -> i64 {
+        if self.moves == 0 {
+            return 0;
+        }
+        self.total_loss / self.moves as i64
+    }
+
+    fn add(&mut self, annotated: &AnnotatedMove) {
+        self.moves += 1;
+        self.total_loss += annotated.centipawn_loss as i64;
+        match annotated.judgement {
+            Some(Judgement::Inaccuracy) => self.inaccuracies += 1,
+            Some(Judgement::Mistake) => self.mistakes += 1,
+            Some(Judgement::Blunder) => self.blunders += 1,
+            None => {}
+        }
+    }
+}
+
+impl std::fmt::Display for PlayerSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "average centipawn loss {}, {} inaccuracies, {} mistakes, {} blunders",
+            self.average_centipawn_loss(),
+            self.inaccuracies,
+            self.mistakes,
+            self.blunders
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AnnotatedGame {
+    pub game: PgnGame,
+    pub moves: Vec<AnnotatedMove>,
+    pub white: PlayerSummary,
+    pub black: PlayerSummary,
+}
+
+struct PositionEvaluation {
+    /// From the side to move's perspective
+    score: i32,
+    pv: Vec<Move>,
+}
+
+fn evaluate_position(
+    bot: &mut GiffiBot,
+    stop: &AtomicBool,
+    board: &ChessBoard,
+    limit: AnalysisLimit,
+) -> PositionEvaluation {
+    let mut board = board.clone();
+    if board.get_legal_moves().is_empty() {
+        let score = if board.is_king_in_check(board.get_turn()) {
+            -MATE
+        } else {
+            0
+        };
+        return PositionEvaluation { score, pv: vec![] };
+    }
+
+    bot.board = board;
+    stop.store(false, Ordering::Relaxed);
+    let result = match limit {
+        AnalysisLimit::Depth(depth) => bot.go_depth(depth),
+        AnalysisLimit::MoveTime(time) => bot.go_timed(time),
+    };
+
+    PositionEvaluation {
+        score: result.info.score.to_search(),
+        pv: result.info.pv,
+    }
+}
+
+#[inline(always)]
+fn capped(score: i32) -> i32 {
+    score.clamp(-LOSS_SCORE_CAP, LOSS_SCORE_CAP)
+}
+
+fn line_to_san(board: &ChessBoard, line: &[Move]) -> Vec<String> {
+    let mut board = board.clone();
+    let mut sans = vec![];
+    for chess_move in line.iter().take(VARIATION_LENGTH) {
+        // lines from the search should always be legal, but don't trust it blindly
+        if !board.get_legal_moves().iter().any(|m| m == chess_move) {
+            break;
+        }
+        sans.push(pgn::move_to_san(&mut board, *chess_move));
+        board.make_move(*chess_move, true);
+    }
+    sans
+}
+
+/// Searches every position of the game and judges each move by its centipawn loss.
+pub fn analyze_game(game: &PgnGame, limit: AnalysisLimit) -> Result<AnnotatedGame, AnalysisError> {
+    let fen = game.get_header("FEN").unwrap_or(STARTPOS_FEN);
+    let mut board = ChessBoard::new();
+    if let Err(error) = board.parse_fen(fen) {
+        return Err(AnalysisError::InvalidFen(format!("{:?}", error)));
+    }
+    let mut fullmove = fen
+        .split_whitespace()
+        .nth(5)
+        .and_then(|n| n.parse::<u32>().ok())
+        .unwrap_or(1);
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut bot = GiffiBot::new(board.clone(), Arc::clone(&stop));
+    bot.set_reporter(SilentReporter);
+
+    // Evaluate every position, including the one after the last move
+    let mut positions = vec![board.clone()];
+    let mut played = vec![];
+    let mut evaluations = vec![evaluate_position(&mut bot, &stop, &board, limit)];
+    for (ply, san) in game.moves.iter().enumerate() {
+        let chess_move = match pgn::san_to_move(&mut board, san) {
+            Some(m) => m,
+            None => {
+                return Err(AnalysisError::IllegalMove {
+                    ply: ply + 1,
+                    san: san.clone(),
+                });
+            }
+        };
+        played.push(chess_move);
+        let _ = board.make_move_uci(&chess_move.to_uci());
+        positions.push(board.clone());
+        evaluations.push(evaluate_position(&mut bot, &stop, &board, limit));
+    }
+
+    let mut annotated = AnnotatedGame {
+        game: game.clone(),
+        moves: vec![],
+        white: PlayerSummary::default(),
+        black: PlayerSummary::default(),
+    };
+
+    for (ply, chess_move) in played.iter().enumerate() {
+        let mut before = positions[ply].clone();
+        let color = before.get_turn();
+        let best = &evaluations[ply];
+        let played_score = -evaluations[ply + 1].score;
+
+        let is_best = best.pv.first() == Some(chess_move);
+        let centipawn_loss = if is_best {
+            0
+        } else {
+            (capped(best.score) - capped(played_score)).max(0)
+        };
+
+        let perspective = if color == PieceColor::White { 1 } else { -1 };
+        let annotated_move = AnnotatedMove {
+            san: pgn::move_to_san(&mut before, *chess_move),
+            color,
+            fullmove,
+            centipawn_loss,
+            judgement: Judgement::from_loss(centipawn_loss),
+            best_line: if is_best {
+                vec![]
+            } else {
+                line_to_san(&before, &best.pv)
+            },
+            best_score: best.score * perspective,
+        };
+
+        if color == PieceColor::White {
+            annotated.white.add(&annotated_move);
+        } else {
+            annotated.black.add(&annotated_move);
+            fullmove += 1;
+        }
+        annotated.moves.push(annotated_move);
+    }
+
+    annotated.game.set_header("Annotator", "GiffiBot");
+    annotated.game.set_header(
+        "WhiteACPL",
+        &annotated.white.average_centipawn_loss().to_string(),
+    );
+    annotated.game.set_header(
+        "BlackACPL",
+        &annotated.black.average_centipawn_loss().to_string(),
+    );
+    Ok(annotated)
+}
+
+/// Score from white's perspective as shown in PGN comments, "+0.35" or "#-3"
+pub fn format_score(score: i32) -> String {
+    if score.abs() >= MATE_THRESHOLD {
+        let mate_in = (MATE - score.abs() + 1) / 2;
+        return format!("#{}", if score > 0 { mate_in } else { -mate_in });
+    }
+    format!("{:+.2}", score as f32 / 100.0)
+}
+
+fn push_wrapped(text: &mut String, line_length: &mut usize, token: &str) {
+    if *line_length > 0 && *line_length + 1 + token.len() > PGN_LINE_WIDTH {
+        text.push('\n');
+        *line_length = 0;
+    } else if *line_length > 0 {
+        text.push(' ');
+        *line_length += 1;
+    }
+    text.push_str(token);
+    *line_length += token.len();
+}
+
+impl AnnotatedGame {
+    pub fn to_pgn(&self) -> String {
+        let mut text = String::new();
+        for (key, value) in &self.game.headers {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            text.push_str(&format!("[{} \"{}\"]\n", key, value));
+        }
+        text.push('\n');
+
+        let mut tokens = vec![format!(
+            "{{ White: {}. Black: {}. }}",
+            self.white, self.black
+        )];
+        let mut needs_number = true;
+        for annotated in &self.moves {
+            let is_white = annotated.color == PieceColor::White;
+            if is_white {
+                tokens.push(format!("{}.", annotated.fullmove));
+            } else if needs_number {
+                tokens.push(format!("{}...", annotated.fullmove));
+            }
+            tokens.push(annotated.san.clone());
+            needs_number = false;
+
+            if let Some(judgement) = annotated.judgement {
+                tokens.push(judgement.nag().to_string());
+                tokens.push(format!(
+                    "{{ {}, loses {:.2}. }}",
+                    judgement.name(),
+                    annotated.centipawn_loss as f32 / 100.0
+                ));
+                tokens.extend(self.variation_tokens(annotated));
+                needs_number = true;
+            }
+        }
+        tokens.push(self.game.result.clone());
+
+        let mut line_length = 0;
+        for token in tokens {
+            push_wrapped(&mut text, &mut line_length, &token);
+        }
+        text.push_str("\n\n");
+        text
+    }
+
+    fn variation_tokens(&self, annotated: &AnnotatedMove) -> Vec<String> {
+        if annotated.best_line.is_empty() {
+            return vec![];
+        }
+
+        let mut tokens = vec![];
+        let mut fullmove = annotated.fullmove;
+        let mut is_white = annotated.color == PieceColor::White;
+        for (i, san) in annotated.best_line.iter().enumerate() {
+            if is_white {
+                tokens.push(format!("{}.", fullmove));
+            } else if i == 0 {
+                tokens.push(format!("{}...", fullmove));
+            }
+            tokens.push(san.clone());
+            if i == 0 {
+                tokens.push(format!("{{ {} }}", format_score(annotated.best_score)));
+            }
+
+            if !is_white {
+                fullmove += 1;
+            }
+            is_white = !is_white;
+        }
+
+        if let Some(first) = tokens.first_mut() {
+            first.insert(0, '(');
+        }
+        if let Some(last) = tokens.last_mut() {
+            last.push(')');
+        }
+        tokens
+    }
+}
+
+fn print_usage() {
+    eprintln!("usage: giffibot analyze <input.pgn> [--depth N | --movetime MS] [--output FILE]");
+}
+
+/// `giffibot analyze <input.pgn> [--depth N | --movetime MS] [--output FILE]`
+pub fn run_cli(args: &[String]) -> Result<(), AnalysisError> {
+    let mut input = None;
+    let mut output = None;
+    let mut limit = AnalysisLimit::Depth(DEFAULT_DEPTH);
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => {
+                let depth = args.next().and_then(|d| d.parse::<i32>().ok());
+                match depth {
+                    Some(depth) if depth > 0 => limit = AnalysisLimit::Depth(depth),
+                    _ => {
+                        print_usage();
+                        return Err(AnalysisError::InvalidArgument(arg.clone()));
+                    }
+                }
+            }
+            "--movetime" => {
+                let time = args.next().and_then(|t| t.parse::<u64>().ok());
+                match time {
+                    Some(time) => limit = AnalysisLimit::MoveTime(Duration::from_millis(time)),
+                    None => {
+                        print_usage();
+                        return Err(AnalysisError::InvalidArgument(arg.clone()));
+                    }
+                }
+            }
+            "--output" | "-o" => match args.next() {
+                Some(path) => output = Some(path.clone()),
+                None => {
+                    print_usage();
+                    return Err(AnalysisError::InvalidArgument(arg.clone()));
+                }
+            },
+            _ if input.is_none() && !arg.starts_with('-') => input = Some(arg.clone()),
+            _ => {
+                print_usage();
+                return Err(AnalysisError::InvalidArgument(arg.clone()));
+            }
+        }
+    }
+
+    let input = match input {
+        Some(input) => input,
+        None => {
+            print_usage();
+            return Err(AnalysisError::InvalidArgument(String::from(
+                "missing input file",
+            )));
+        }
+    };
+
+    let games = pgn::parse_pgn(&std::fs::read_to_string(&input)?);
+    let mut annotated_pgn = String::new();
+    for (i, game) in games.iter().enumerate() {
+        eprintln!(
+            "[{}/{}] analyzing {} - {}",
+            i + 1,
+            games.len(),
+            game.get_header("White").unwrap_or("?"),
+            game.get_header("Black").unwrap_or("?")
+        );
+
+        match analyze_game(game, limit) {
+            Ok(annotated) => {
+                eprintln!("  White: {}", annotated.white);
+                eprintln!("  Black: {}", annotated.black);
+                annotated_pgn.push_str(&annotated.to_pgn());
+            }
+            Err(error) => {
+                // Keep going, one broken game shouldn't throw away the whole batch
+                eprintln!("  skipped: {:?}", error);
+            }
+        }
+    }
+
+    match output {
+        Some(output) => {
+            std::fs::write(&output, annotated_pgn)?;
+            eprintln!("wrote {}", output);
+        }
+        None => print!("{}", annotated_pgn),
+    }
+    Ok(())
+}
@@ -0,0 +1,123 @@
+//! Fixed depth search over built-in positions, the total node count is a signature of the search.
+//!
+//! Any change to the search that isn't purely a speedup changes the node count, so
+//! commits touching the search put `Bench: <nodes>` in their message.
+
+use std::sync::{atomic::AtomicBool, Arc};
+use std::time::{Duration, Instant};
+
+use crate::chessbot::{
+    internal_iteration::InternalIteration, reporter::SilentReporter, GiffiBot, TableStats,
+};
+use bitschess::prelude::*;
+
+pub const DEFAULT_BENCH_DEPTH: i32 = 6;
+
+/// Openings, middlegames and endgames, the tricky move generation positions included.
+const BENCH_POSITIONS: [&str; 16] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+    "rnbqkb1r/pp2pppp/3p1n2/8/3NP3/8/PPP2PPP/RNBQKB1R w KQkq - 1 5",
+    "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP1QBPPP/R3KB1R w KQ - 2 9",
+    "2rq1rk1/pb1nbppp/1p2pn2/2pp4/2PP4/1PN1PN2/PB1QBPPP/2R2RK1 w - - 4 12",
+    "r2q1rk1/1b1nbppp/p2ppn2/1p6/3NP3/1BN1BP2/PPPQ2PP/2KR3R w - - 4 12",
+    "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1",
+    "8/8/4k3/3p4/3P4/4K3/8/8 w - - 0 1",
+    "8/5pk1/6p1/7p/7P/6P1/5PK1/3r4 b - - 0 40",
+    "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+    "8/8/8/4k3/8/8/2Q5/4K3 w - - 0 1",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct BenchResult {
+    pub depth: i32,
+    pub internal_iteration: InternalIteration,
+    /// Node count of every position, in the order of the built-in list
+    pub nodes_per_position: Vec<u64>,
+    pub nodes: u64,
+    pub time: Duration,
+    /// Transposition table use over the whole run
+    pub tt: TableStats,
+}
+
+impl BenchResult {
+    pub fn nps(&self) -> u64 {
+        (self.nodes as f64 / self.time.as_secs_f64().max(0.001)) as u64
+    }
+}
+
+/// Searches every built-in position to `depth` with a cleared transposition table, printing progress.
+pub fn bench(depth: i32) -> BenchResult {
+    bench_with(depth, InternalIteration::default())
+}
+
+/// `bench` with the given internal iteration, to compare the node counts of each.
+pub fn bench_with(depth: i32, internal_iteration: InternalIteration) -> BenchResult {
+    let mut bot = GiffiBot::new(ChessBoard::new(), Arc::new(AtomicBool::new(false)));
+    bot.set_reporter(SilentReporter);
+    bot.set_internal_iteration(internal_iteration);
+
+    let mut result = BenchResult {
+        depth,
+        internal_iteration,
+        ..Default::default()
+    };
+    let start = Instant::now();
+    for (i, fen) in BENCH_POSITIONS.iter().enumerate() {
+        // every position starts from the same state, so the node count doesn't depend on the order
+        bot.new_game();
+        bot.board.parse_fen(fen).expect("valid fen");
+
+        let search = bot.go_depth(depth);
+        println!(
+            "position {:>2}/{} nodes {:>10} {}",
+            i + 1,
+            BENCH_POSITIONS.len(),
+            search.info.nodes,
+            fen
+        );
+        result.nodes_per_position.push(search.info.nodes);
+        result.nodes += search.info.nodes;
+    }
+    result.time = start.elapsed();
+    result.tt = bot.tt_stats();
+    result
+}
+
+pub fn print_summary(result: &BenchResult) {
+    println!("===========================");
+    println!("Depth           : {}", result.depth);
+    println!("Internal iter.  : {}", result.internal_iteration);
+    println!("Total time (ms) : {}", result.time.as_millis());
+    println!("Nodes searched  : {}", result.nodes);
+    println!("Nodes/second    : {}", result.nps());
+    println!("TT entries/MB   : {}", result.tt.entries_per_mb);
+    println!("TT hit rate     : {:.1}%", result.tt.hit_rate() * 100.0);
+}
+
+/// `giffibot bench [depth] [off|reduction|deepening]`
+pub fn run_cli(args: &[String]) -> Result<(), String> {
+    let depth = match args.first() {
+        Some(depth) => match depth.parse::<i32>() {
+            Ok(depth) if depth > 0 => depth,
+            _ => {
+                return Err(format!(
+                    "invalid depth '{}', usage: giffibot bench [depth] [off|reduction|deepening]",
+                    depth
+                ))
+            }
+        },
+        None => DEFAULT_BENCH_DEPTH,
+    };
+    let internal_iteration = match args.get(1) {
+        Some(mode) => mode.parse::<InternalIteration>()?,
+        None => InternalIteration::default(),
+    };
+    print_summary(&bench_with(depth, internal_iteration));
+    Ok(())
+}
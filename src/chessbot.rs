@@ -1,38 +1,104 @@
+pub mod eval_trace;
+pub mod extensions;
 pub mod go;
+pub mod internal_iteration;
 pub mod masks;
 pub mod move_ordering;
+pub mod reporter;
+pub mod root;
+pub mod search_info;
+pub mod skill;
 pub mod value;
 
+mod pv_table;
+#[cfg(test)]
+mod test_util;
 mod transposition_table;
+use internal_iteration::InternalIteration;
+use pv_table::PvTable;
+use reporter::{CallbackReporter, SearchReporter, UciReporter};
+use root::RootMove;
+use search_info::SearchInfo;
+use skill::{Rng, Skill};
 use transposition_table::{NodeKind, TranspositionTable};
+pub use transposition_table::{TableFileError, TableStats};
 
 use std::collections::VecDeque;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
+    mpsc::Sender,
     Arc,
 };
 
 use bitschess::prelude::*;
 
-const MAX_MOVE_EXTENSIONS: u8 = 15;
+/// Pawns on it are a step away from promoting, white's 7th rank and black's 2nd.
+const SEVENTH_RANK: [u64; 2] = [0x00FF_0000_0000_0000, 0x0000_0000_0000_FF00];
+/// Quiet checks are searched at the first quiescence ply.
+const QSEARCH_CHECKS: bool = true;
+/// A capture has to be able to get back within this of alpha, or quiescence skips it.
+const DELTA_MARGIN: i32 = 200;
+/// Reverse futility pruning: up to this depth a non-PV node fails high when the static eval
+/// is at least `RFP_MARGIN` per ply of depth above beta.
+const RFP_MAX_DEPTH: i32 = 6;
+const RFP_MARGIN: i32 = 120;
+/// Razoring: up to this depth a node with the static eval `RAZOR_MARGIN` per ply of depth
+/// below alpha is only searched by quiescence.
+const RAZOR_MAX_DEPTH: i32 = 2;
+const RAZOR_MARGIN: i32 = 300;
+/// Futility pruning: quiet moves are skipped when the static eval plus the margin of the
+/// remaining depth doesn't reach alpha.
+const FUTILITY_MAX_DEPTH: i32 = 3;
+const FUTILITY_MARGINS: [i32; FUTILITY_MAX_DEPTH as usize + 1] = [0, 200, 350, 550];
+/// Nodes between looking at the clock for `SearchReporter::progress`.
+const PROGRESS_CHECK_NODES: u64 = 4096;
 
 pub(crate) const MAX_DEPTH: i32 = 256; // there is no way we're reaching depth 256 in our lifetime :D
 pub(crate) const MATE: i32 = 30_000;
 pub(crate) const MATE_THRESHOLD: i32 = MATE - 1000;
 
-#[derive(Debug, Clone)]
+/// Not `Clone`: the bot owns its `SearchReporter`, which may hold a callback or a channel
+/// that can't be duplicated. Make a new bot with `GiffiBot::new` instead.
+#[derive(Debug)]
 pub struct GiffiBot {
     pub board: ChessBoard,
     search_cancelled: Arc<AtomicBool>,
 
     iterations: u64,
+    node_limit: u64,
     completed_depth: i32,
+    seldepth: i32,
+    /// Best line of the last completed iteration, followed by the next one.
     pub pv: VecDeque<Move>,
+    /// The current node is on `pv`, reached by playing its first moves
+    follow_pv: bool,
+    pv_table: PvTable,
+    /// Restricts the moves searched at the root, empty searches every legal move.
+    searchmoves: Vec<Move>,
+    root_moves: Vec<RootMove>,
     killers: [Move; MAX_DEPTH as usize],
+    skill: Skill,
+    rng: Rng,
+    /// Centipawns a draw is worth less than zero to the side to move at the root
+    contempt: i32,
+    /// Hashes of the positions from the root to the current node, for repetitions in the tree
+    search_path: Vec<u64>,
+    /// Draws returned by the search so far, a node whose subtree returned one has a score
+    /// that depends on the path to it
+    draws: u64,
+    /// Parallel to `search_path`, the move made if it was a capture, otherwise `Move(0)`
+    capture_path: Vec<Move>,
+    /// Plies of extensions a path from the root may add, grows with the iteration depth
+    extension_budget: u8,
+    internal_iteration: InternalIteration,
 
     tt: TranspositionTable,
 
     search_begin: std::time::Instant,
+    /// When `SearchReporter::progress` was last called
+    last_progress: std::time::Instant,
+    currmove_delay: std::time::Duration,
+    reporter: Box<dyn SearchReporter>,
 }
 
 impl GiffiBot {
@@ -41,18 +107,95 @@ impl GiffiBot {
             board,
 
             iterations: 0,
+            node_limit: u64::MAX,
             search_cancelled: stop_search,
             completed_depth: 0,
+            seldepth: 0,
             pv: VecDeque::new(),
+            follow_pv: false,
+            pv_table: PvTable::new(),
+            searchmoves: Vec::new(),
+            root_moves: Vec::new(),
             killers: [Move(0); MAX_DEPTH as usize],
+            skill: Skill::default(),
+            rng: Rng::from_time(),
+            contempt: 0,
+            search_path: Vec::new(),
+            draws: 0,
+            capture_path: Vec::new(),
+            extension_budget: 0,
+            internal_iteration: InternalIteration::default(),
 
             tt: TranspositionTable::new(),
 
             search_begin: std::time::Instant::now(),
+            last_progress: std::time::Instant::now(),
+            currmove_delay: root::DEFAULT_CURRMOVE_DELAY,
+            reporter: Box::new(UciReporter::new()),
         }
     }
 
+    /// Plays weaker than full strength, see `Skill`.
+    pub fn set_skill(&mut self, skill: Skill) {
+        self.skill = skill;
+    }
+
+    /// Positive values make the bot avoid draws, negative ones seek them.
+    pub fn set_contempt(&mut self, contempt: i32) {
+        self.contempt = contempt;
+    }
+
+    /// Where the search output goes, prints UCI to stdout by default.
+    pub fn set_reporter<R: SearchReporter + 'static>(&mut self, reporter: R) {
+        self.reporter = Box::new(reporter);
+    }
+
+    /// `callback` is called with the info of every completed iteration, nothing is printed.
+    pub fn set_info_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(&SearchInfo) + Send + 'static,
+    {
+        self.set_reporter(CallbackReporter(callback));
+    }
+
+    /// Sends the info of every completed iteration through the channel, nothing is printed.
+    pub fn set_info_sender(&mut self, sender: Sender<SearchInfo>) {
+        self.set_reporter(CallbackReporter::from_sender(sender));
+    }
+
+    /// Forgets everything learned from the previous game.
+    pub fn new_game(&mut self) {
+        self.tt.clear();
+        self.killers = [Move(0); MAX_DEPTH as usize];
+    }
+
+    /// Saves the transposition table, `load_tt` continues from it in a later session.
+    ///
+    /// # Errors
+    /// If the file can't be written.
+    pub fn save_tt(&self, path: &std::path::Path) -> Result<(), TableFileError> {
+        self.tt.save(path)
+    }
+
+    /// # Errors
+    /// If the file can't be read or was saved by an incompatible table or engine version.
+    pub fn load_tt(&mut self, path: &std::path::Path) -> Result<(), TableFileError> {
+        self.tt.load(path)
+    }
+
+    /// Transposition table density and hit rate, counted since the bot was made.
+    pub fn tt_stats(&self) -> TableStats {
+        self.tt.stats()
+    }
+
     pub const fn is_end_game(&self) -> bool {
+        // Trigger under 4 rooks
+        const MATERIAL_4_ROOKS: i32 = value::get_piece_value(PieceType::Rook) * 4;
+        self.phase_material() < MATERIAL_4_ROOKS
+    }
+
+    /// Bishop, rook and queen material of both sides.
+    pub const fn phase_material(&self) -> i32 {
         let bishops = self.board.bitboards[PieceType::Bishop.get_side_index(PieceColor::White)]
             | self.board.bitboards[PieceType::Bishop.get_side_index(PieceColor::Black)];
         let rooks = self.board.bitboards[PieceType::Rook.get_side_index(PieceColor::White)]
@@ -60,41 +203,169 @@ impl GiffiBot {
         let queens = self.board.bitboards[PieceType::Queen.get_side_index(PieceColor::White)]
             | self.board.bitboards[PieceType::Queen.get_side_index(PieceColor::Black)];
 
-        // Trigger under 4 rooks
-        const MATERIAL_4_ROOKS: i32 = value::get_piece_value(PieceType::Rook) * 4;
-        let material_count = (bishops.count_ones() as i32
-            * value::get_piece_value(PieceType::Bishop))
+        (bishops.count_ones() as i32 * value::get_piece_value(PieceType::Bishop))
             + (rooks.count_ones() as i32 * value::get_piece_value(PieceType::Rook))
-            + (queens.count_ones() as i32 * value::get_piece_value(PieceType::Queen));
-        material_count < MATERIAL_4_ROOKS
+            + (queens.count_ones() as i32 * value::get_piece_value(PieceType::Queen))
+    }
+
+    /// Draw score for the side to move, contempt counts against the side to move at the root.
+    #[inline(always)]
+    fn draw_score(&self, ply_from_root: i32) -> i32 {
+        if ply_from_root % 2 == 0 {
+            -self.contempt
+        } else {
+            self.contempt
+        }
+    }
+
+    /// A repetition, a draw by rule or a stalemate, counted in `draws`.
+    #[inline(always)]
+    fn draw(&mut self, ply_from_root: i32) -> i32 {
+        self.draws += 1;
+        self.draw_score(ply_from_root)
+    }
+
+    /// Stores in the TT, unless a draw was returned below the node since `draws_before`.
+    ///
+    /// With contempt a draw scores by who moves at the root, the same position reached from
+    /// another root or at the other parity is worth something else. Only the move of such
+    /// a node is kept, in the entry the position already has.
+    #[allow(clippy::too_many_arguments)]
+    #[inline(always)]
+    fn store_in_tt(
+        &mut self,
+        kind: NodeKind,
+        hash: u64,
+        depth: i32,
+        ply_from_root: i32,
+        score: i32,
+        best_move: Move,
+        draws_before: u64,
+    ) {
+        if self.contempt != 0 && self.draws != draws_before {
+            self.tt.update_move(hash, best_move);
+            return;
+        }
+        self.tt
+            .store_evaluation(kind, hash, depth, ply_from_root, score, best_move);
+    }
+
+    /// The current position already occurred on the path from the root.
+    ///
+    /// One repetition inside the tree is enough, the side repeating could repeat again.
+    /// Earlier game positions are left to `is_draw`.
+    #[inline(always)]
+    fn is_repetition(&self) -> bool {
+        let hash = self.board.zobrist_hash;
+        self.search_path
+            .iter()
+            .rev()
+            .skip(2)
+            .step_by(2)
+            .any(|earlier| *earlier == hash)
+    }
+
+    #[inline(always)]
+    fn make_search_move(&mut self, chess_move: Move) {
+        let capture = self
+            .board
+            .get_piece(chess_move.get_to_idx())
+            .get_piece_type()
+            != PieceType::None;
+        self.capture_path
+            .push(if capture { chess_move } else { Move(0) });
+        self.board.make_move(chess_move, true);
+        self.search_path.push(self.board.zobrist_hash);
+    }
+
+    #[inline(always)]
+    fn unmake_search_move(&mut self) {
+        self.search_path.pop();
+        self.capture_path.pop();
+        let _ = self.board.unmake_move();
+    }
+
+    /// Looks at the clock for `SearchReporter::progress` every `PROGRESS_CHECK_NODES` nodes,
+    /// also in the passes that can't be cancelled.
+    #[inline(always)]
+    fn check_progress(&mut self) {
+        if self.iterations % PROGRESS_CHECK_NODES == 0 {
+            self.report_progress();
+        }
     }
 
+    /// Checks for 'stop' and the node limit, once hit the whole search unwinds.
+    #[inline(always)]
+    fn should_stop(&mut self) -> bool {
+        if self.iterations >= self.node_limit {
+            self.search_cancelled.store(true, Ordering::Relaxed);
+        }
+        self.search_cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Quiescence search: captures and queen promotions until the position is quiet.
+    ///
+    /// In check every evasion is searched instead of standing pat, and at the first
+    /// quiescence ply (`qply` 0) quiet checks are searched too.
     fn search_all_captures(
         &mut self,
         mut alpha: i32,
         beta: i32,
         cancellable: bool,
         ply_from_root: i32,
+        qply: i32,
     ) -> i32 {
-        if cancellable && self.search_cancelled.load(Ordering::Relaxed) {
+        self.check_progress();
+        if cancellable && self.should_stop() {
             return 0;
         }
+        self.seldepth = self.seldepth.max(ply_from_root);
 
-        let mut eval = self.evaluate();
-        if eval >= beta {
-            return beta;
+        if self.board.is_king_in_check(self.board.get_turn()) {
+            return self.search_evasions(alpha, beta, cancellable, ply_from_root, qply);
         }
-        alpha = std::cmp::max(alpha, eval);
 
-        let mut captures = self.board.get_legal_captures();
-        self.order_moves(&mut captures, ply_from_root);
+        let stand_pat = self.evaluate();
+        if stand_pat >= beta {
+            return beta;
+        }
+        alpha = std::cmp::max(alpha, stand_pat);
+        // with little material left positional terms swing too much for delta pruning
+        let delta_pruning = !self.is_end_game();
+
+        // quiet queen promotions, plus quiet checks at the first ply, come out of the same
+        // move generation as the captures
+        let quiet_moves = self.can_promote() || (QSEARCH_CHECKS && qply == 0);
+        let mut moves = if quiet_moves {
+            self.board.get_legal_moves()
+        } else {
+            self.board.get_legal_captures()
+        };
+        self.order_moves(&mut moves, ply_from_root);
 
-        for m in captures {
-            self.iterations += 1;
-            self.board.make_move(m, true);
-            eval = -self.search_all_captures(-beta, -alpha, cancellable, ply_from_root + 1);
-            let _ = self.board.unmake_move();
+        for m in moves.iter() {
+            let promotion = m.get_flag() == MoveFlag::PromoteQueen;
+            if !self.is_capture(*m) {
+                let quiet_check = QSEARCH_CHECKS
+                    && qply == 0
+                    && !promotion
+                    && !Self::is_underpromotion(*m)
+                    && self.gives_check(*m);
+                if !promotion && !quiet_check {
+                    continue;
+                }
+            } else if delta_pruning && !promotion {
+                // en passant lands on an empty square, it's still a pawn
+                let captured = match self.board.get_piece(m.get_to_idx()).get_piece_type() {
+                    PieceType::None => value::get_piece_value(PieceType::Pawn),
+                    piece_type => value::get_piece_value(piece_type),
+                };
+                if stand_pat + captured + DELTA_MARGIN < alpha {
+                    continue;
+                }
+            }
 
+            let eval = self.qsearch_move(*m, alpha, beta, cancellable, ply_from_root, qply);
             if eval >= beta {
                 return beta;
             }
@@ -104,93 +375,281 @@ impl GiffiBot {
         alpha
     }
 
+    /// Quiescence while in check, standing pat isn't an option so every legal move is searched.
+    fn search_evasions(
+        &mut self,
+        mut alpha: i32,
+        beta: i32,
+        cancellable: bool,
+        ply_from_root: i32,
+        qply: i32,
+    ) -> i32 {
+        let mut evasions = self.board.get_legal_moves();
+        if evasions.is_empty() {
+            return -MATE + ply_from_root;
+        }
+        self.order_moves(&mut evasions, ply_from_root);
+
+        for m in evasions.iter() {
+            let eval = self.qsearch_move(*m, alpha, beta, cancellable, ply_from_root, qply);
+            if eval >= beta {
+                return beta;
+            }
+            alpha = std::cmp::max(alpha, eval);
+        }
+        alpha
+    }
+
+    #[inline(always)]
+    fn qsearch_move(
+        &mut self,
+        chess_move: Move,
+        alpha: i32,
+        beta: i32,
+        cancellable: bool,
+        ply_from_root: i32,
+        qply: i32,
+    ) -> i32 {
+        self.iterations += 1;
+        self.board.make_move(chess_move, true);
+        let eval =
+            -self.search_all_captures(-beta, -alpha, cancellable, ply_from_root + 1, qply + 1);
+        let _ = self.board.unmake_move();
+        eval
+    }
+
+    /// Side to move has a pawn a step away from promoting.
+    #[inline(always)]
+    fn can_promote(&self) -> bool {
+        let color = self.board.get_turn();
+        let pawns = self.board.bitboards[PieceType::Pawn.get_side_index(color)];
+        pawns & SEVENTH_RANK[color as usize] != 0
+    }
+
+    /// Takes a piece, en passant included.
+    #[inline(always)]
+    fn is_capture(&self, chess_move: Move) -> bool {
+        let from = chess_move.get_from_idx();
+        let to = chess_move.get_to_idx();
+        if self.board.get_piece(to).get_piece_type() != PieceType::None {
+            return true;
+        }
+        // a pawn only changes file by capturing
+        self.board.get_piece(from).get_piece_type() == PieceType::Pawn
+            && BoardHelper::get_file(from as i32) != BoardHelper::get_file(to as i32)
+    }
+
+    /// The side to move gives check by playing `chess_move`, worked out on the bitboards
+    /// without making the move. Castling and en passant move a second piece, those are made.
+    fn gives_check(&mut self, chess_move: Move) -> bool {
+        let flag = chess_move.get_flag();
+        let moved = self
+            .board
+            .get_piece(chess_move.get_from_idx())
+            .get_piece_type();
+        let en_passant = moved == PieceType::Pawn
+            && self.is_capture(chess_move)
+            && self
+                .board
+                .get_piece(chess_move.get_to_idx())
+                .get_piece_type()
+                == PieceType::None;
+        if flag == MoveFlag::Castle || en_passant {
+            self.board.make_move(chess_move, true);
+            let gives_check = self.board.is_king_in_check(self.board.get_turn());
+            let _ = self.board.unmake_move();
+            return gives_check;
+        }
+
+        let us = self.board.get_turn();
+        let king = self.board.bitboards[PieceType::King.get_side_index(us.flipped())];
+        if king == 0 {
+            return false;
+        }
+        let king_square = BoardHelper::bitscan_forward(king);
+        let piece_type = match flag {
+            MoveFlag::PromoteQueen => PieceType::Queen,
+            MoveFlag::PromoteRook => PieceType::Rook,
+            MoveFlag::PromoteBishop => PieceType::Bishop,
+            MoveFlag::PromoteKnight => PieceType::Knight,
+            _ => moved,
+        };
+        let from = chess_move.get_from_idx() as i32;
+        let to = chess_move.get_to_idx() as i32;
+
+        let files =
+            (BoardHelper::get_file(king_square) as i32 - BoardHelper::get_file(to) as i32).abs();
+        let ranks = BoardHelper::get_rank(king_square) as i32 - BoardHelper::get_rank(to) as i32;
+        let forward = if us == PieceColor::White { 1 } else { -1 };
+        match piece_type {
+            PieceType::Pawn if ranks == forward && files == 1 => return true,
+            PieceType::Knight if files * ranks.abs() == 2 => return true,
+            _ => {}
+        }
+
+        // sliders, the moved one from its new square and the ones it uncovers
+        let from_bit = 1u64 << from;
+        let to_bit = 1u64 << to;
+        let occupied =
+            ((self.board.side_bitboards[0] | self.board.side_bitboards[1]) & !from_bit) | to_bit;
+        let ours =
+            |piece_type: PieceType| self.board.bitboards[piece_type.get_side_index(us)] & !from_bit;
+        let mut straight = ours(PieceType::Rook) | ours(PieceType::Queen);
+        let mut diagonal = ours(PieceType::Bishop) | ours(PieceType::Queen);
+        if piece_type == PieceType::Rook || piece_type == PieceType::Queen {
+            straight |= to_bit;
+        }
+        if piece_type == PieceType::Bishop || piece_type == PieceType::Queen {
+            diagonal |= to_bit;
+        }
+        magics::get_rook_magic(king_square, occupied) & straight != 0
+            || magics::get_bishop_magic(king_square, occupied) & diagonal != 0
+    }
+
+    /// Neither a capture, en passant included, nor a promotion.
+    #[inline(always)]
+    fn is_quiet(&self, chess_move: Move) -> bool {
+        let is_promotion =
+            chess_move.get_flag() == MoveFlag::PromoteQueen || Self::is_underpromotion(chess_move);
+        !self.is_capture(chess_move) && !is_promotion
+    }
+
+    #[inline(always)]
+    fn is_underpromotion(chess_move: Move) -> bool {
+        let flag = chess_move.get_flag();
+        flag == MoveFlag::PromoteRook
+            || flag == MoveFlag::PromoteBishop
+            || flag == MoveFlag::PromoteKnight
+    }
+
     fn order_moves(&mut self, moves: &mut MoveContainer, ply: i32) {
+        let pv_move = self.pv_move(moves, ply);
         move_ordering::MoveOrdering::order_moves(
             &self.board,
             &self.tt,
             moves,
-            self.pv.pop_front(),
-            self.killers[ply.min(MAX_DEPTH) as usize],
+            pv_move,
+            self.killers[ply.min(MAX_DEPTH - 1) as usize],
         );
     }
 
+    /// The previous iteration's move at `ply`, as long as the search is still following its line.
+    fn pv_move(&mut self, moves: &MoveContainer, ply: i32) -> Option<Move> {
+        if !self.follow_pv {
+            return None;
+        }
+        let pv_move = self
+            .pv
+            .get(ply as usize)
+            .copied()
+            .filter(|pv_move| moves.iter().any(|m| m == pv_move));
+        if pv_move.is_none() {
+            self.follow_pv = false;
+        }
+        pv_move
+    }
+
     fn zw_search(&mut self, beta: i32, depth: i32, ply_from_root: i32, cancellable: bool) -> i32 {
-        if self.search_cancelled.load(Ordering::Relaxed) {
+        self.check_progress();
+        if self.should_stop() {
             return 0;
         }
+        self.seldepth = self.seldepth.max(ply_from_root);
 
+        if self.is_repetition() {
+            return self.draw(ply_from_root);
+        }
         let hash = self.board.zobrist_hash;
+        let draws_before = self.draws;
 
         if depth > 0 {
-            if let Some((score, _)) = self.tt.probe_hash(
-                hash,
-                depth,
-                (beta - 1).saturating_add(ply_from_root),
-                beta.saturating_add(ply_from_root),
-            ) {
-                return score.saturating_sub(ply_from_root);
+            if let Some((score, _)) = self
+                .tt
+                .probe_hash(hash, depth, ply_from_root, beta - 1, beta)
+            {
+                return score;
             }
         }
         if depth == 0 {
-            return self.search_all_captures(beta - 1, beta, cancellable, ply_from_root);
+            return self.search_all_captures(beta - 1, beta, cancellable, ply_from_root, 0);
         }
 
         if self.board.is_draw() {
-            return 0;
+            return self.draw(ply_from_root);
         }
 
+        let in_check = self.board.is_king_in_check(self.board.get_turn());
         let mut moves = self.board.get_legal_moves();
         if moves.is_empty() {
-            if self.board.is_king_in_check(self.board.get_turn()) {
+            if in_check {
                 return -MATE + ply_from_root;
             }
-            return 0; // draw
+            return self.draw(ply_from_root);
+        }
+
+        // the static eval means nothing in check, and nothing next to a mate score
+        let static_eval = if !in_check
+            && depth <= RFP_MAX_DEPTH.max(RAZOR_MAX_DEPTH).max(FUTILITY_MAX_DEPTH)
+            && beta.abs() < MATE_THRESHOLD
+        {
+            Some(self.evaluate())
+        } else {
+            None
+        };
+        if let Some(static_eval) = static_eval {
+            // reverse futility: so far above beta that no move of ours is going to lose it all
+            if depth <= RFP_MAX_DEPTH && static_eval - RFP_MARGIN * depth >= beta {
+                return beta;
+            }
+            // razoring: so far below that only a capture could help, let quiescence decide
+            if depth <= RAZOR_MAX_DEPTH && static_eval + RAZOR_MARGIN * depth < beta - 1 {
+                let score = self.search_all_captures(beta - 1, beta, cancellable, ply_from_root, 0);
+                if score < beta {
+                    return beta - 1;
+                }
+            }
         }
+        let futility_pruning = static_eval.map_or(false, |static_eval| {
+            depth <= FUTILITY_MAX_DEPTH && static_eval + FUTILITY_MARGINS[depth as usize] < beta
+        });
 
         self.order_moves(&mut moves, ply_from_root);
         let mut best_move = Move(0);
         for m in moves {
+            // a quiet move can't make up the difference, unless it gives check
+            if futility_pruning && self.is_quiet(m) && !self.gives_check(m) {
+                continue;
+            }
             self.iterations += 1;
-            self.board.make_move(m, true);
+            self.make_search_move(m);
             let eval = -self.zw_search(1 - beta, depth - 1, ply_from_root + 1, cancellable);
-            let _ = self.board.unmake_move();
+            self.unmake_search_move();
             if eval >= beta {
                 best_move = m;
-                self.tt.store_evaluation(
+                self.store_in_tt(
                     NodeKind::LowerBound,
                     hash,
                     depth,
-                    beta.saturating_add(ply_from_root),
+                    ply_from_root,
+                    beta,
                     best_move,
+                    draws_before,
                 );
                 return beta; // fail-hard beta-cutoff
             }
         }
-        self.tt.store_evaluation(
+        self.store_in_tt(
             NodeKind::UpperBound,
             hash,
             depth,
-            (beta - 1).saturating_add(ply_from_root),
+            ply_from_root,
+            beta - 1,
             best_move,
+            draws_before,
         );
         beta - 1 // fail-hard, return alpha
     }
 
-    pub fn get_extension(&self, chess_move: Move, extension_count: u8) -> u8 {
-        if extension_count > MAX_MOVE_EXTENSIONS {
-            return 0;
-        }
-
-        if self.board.is_king_in_check(self.board.turn) {
-            return 1;
-        }
-        if chess_move.get_flag() == MoveFlag::PromoteQueen {
-            return 1;
-        }
-
-        0
-    }
-
     // https://www.reddit.com/r/chessprogramming/comments/m2m048/how_does_a_triangular_pvtable_work/
     fn search(
         &mut self,
@@ -198,38 +657,36 @@ impl GiffiBot {
         beta: i32,
         depth: i32,
         ply_from_root: i32,
-        line: &mut VecDeque<Move>,
         extension_count: u8,
         cancellable: bool,
     ) -> i32 {
-        if cancellable && self.search_cancelled.load(Ordering::Relaxed) {
+        self.pv_table.clear(ply_from_root);
+        self.check_progress();
+        if cancellable && self.should_stop() {
             return 0;
         }
+        self.seldepth = self.seldepth.max(ply_from_root);
+
+        if self.is_repetition() {
+            return self.draw(ply_from_root);
+        }
 
         let original_alpha = alpha;
         let hash = self.board.zobrist_hash;
+        let draws_before = self.draws;
 
         if depth > 0 {
-            if let Some((score, tt_move)) = self.tt.probe_hash(
-                hash,
-                depth,
-                alpha.saturating_add(ply_from_root),
-                beta.saturating_add(ply_from_root),
-            ) {
-                if tt_move != Move(0) {
-                    line.push_front(tt_move);
-                }
-                return score.saturating_sub(ply_from_root);
+            if let Some((score, _)) = self.tt.probe_hash(hash, depth, ply_from_root, alpha, beta) {
+                return score;
             }
         }
 
         if depth == 0 {
-            line.clear();
-            return self.search_all_captures(alpha, beta, cancellable, ply_from_root);
+            return self.search_all_captures(alpha, beta, cancellable, ply_from_root, 0);
         }
 
         if self.board.is_draw() {
-            return 0;
+            return self.draw(ply_from_root);
         }
 
         let mut moves = self.board.get_legal_moves();
@@ -238,28 +695,34 @@ impl GiffiBot {
             if self.board.is_king_in_check(self.board.get_turn()) {
                 return -MATE + ply_from_root; // adding the distance from root, favours a mate which is closer in moves.
             }
-            return 0; // draw
+            return self.draw(ply_from_root);
         }
 
+        let depth = self.internal_iteration(
+            alpha,
+            beta,
+            depth,
+            ply_from_root,
+            extension_count,
+            cancellable,
+        );
         self.order_moves(&mut moves, ply_from_root);
+        let singular_move = self.singular_move(&moves, depth, ply_from_root, cancellable);
 
         let mut best_move = Move(0);
-        let mut pv = VecDeque::new();
         let mut do_pv_search = true;
         for m in moves.iter() {
-            let extension = self.get_extension(*m, extension_count);
+            let extension = self.get_extension(*m, extension_count, singular_move == Some(*m));
 
             self.iterations += 1;
-            self.board.make_move(*m, true);
+            self.make_search_move(*m);
             let mut eval;
             if do_pv_search {
-                pv.clear();
                 eval = -self.search(
                     -beta,
                     -alpha,
                     depth - 1 + (extension as i32),
                     ply_from_root + 1,
-                    &mut pv,
                     extension_count + extension,
                     cancellable,
                 );
@@ -271,22 +734,19 @@ impl GiffiBot {
                 // proof that the move is bad
                 eval = -self.zw_search(-alpha, depth - 1, ply_from_root + 1, cancellable);
                 if eval > alpha {
-                    let mut re_pv = VecDeque::new();
                     eval = -self.search(
                         -beta,
                         -alpha,
                         depth - 1 + (extension as i32),
                         ply_from_root + 1,
-                        &mut re_pv,
                         extension_count + extension,
                         cancellable,
                     );
-                    if eval > alpha {
-                        pv = re_pv;
-                    }
                 }
             }
-            let _ = self.board.unmake_move();
+            self.unmake_search_move();
+            // only the first move of a node on the previous PV continues along it
+            self.follow_pv = false;
 
             if self.search_cancelled.load(Ordering::Relaxed) {
                 return 0;
@@ -294,24 +754,18 @@ impl GiffiBot {
 
             if eval >= beta {
                 best_move = *m;
-                self.tt.store_evaluation(
+                self.store_in_tt(
                     NodeKind::LowerBound,
                     hash,
                     depth,
-                    beta.saturating_add(ply_from_root),
+                    ply_from_root,
+                    beta,
                     best_move,
+                    draws_before,
                 );
 
-                let is_capture =
-                    self.board.get_piece(m.get_to_idx()).get_piece_type() != PieceType::None;
-
-                let is_promotion = m.get_flag() == MoveFlag::PromoteBishop
-                    || m.get_flag() == MoveFlag::PromoteKnight
-                    || m.get_flag() == MoveFlag::PromoteRook
-                    || m.get_flag() == MoveFlag::PromoteQueen;
-
-                if !is_capture && !is_promotion {
-                    self.killers[ply_from_root.min(MAX_DEPTH) as usize] = *m;
+                if self.is_quiet(*m) {
+                    self.killers[ply_from_root.min(MAX_DEPTH - 1) as usize] = *m;
                 }
                 return beta;
             }
@@ -319,7 +773,7 @@ impl GiffiBot {
                 do_pv_search = false;
                 alpha = eval;
                 best_move = *m;
-                pv.insert(0, *m);
+                self.pv_table.update(ply_from_root, *m);
             }
         }
 
@@ -328,15 +782,225 @@ impl GiffiBot {
         } else {
             NodeKind::UpperBound
         };
-        self.tt.store_evaluation(
+        self.store_in_tt(
             kind,
             hash,
             depth,
-            alpha.saturating_add(ply_from_root),
+            ply_from_root,
+            alpha,
             best_move,
+            draws_before,
         );
 
-        *line = pv;
         alpha
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::test_util::{bot_at, find_move};
+    use super::*;
+
+    fn play(bot: &mut GiffiBot, uci: &str) {
+        let chess_move = find_move(bot, uci);
+        bot.make_search_move(chess_move);
+    }
+
+    #[test]
+    fn contempt_test1() {
+        // stalemated, a draw is worth less than zero to the side to move at the root
+        let mut bot = bot_at("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1");
+        bot.set_contempt(50);
+        assert_eq!(bot.search(-i32::MAX, i32::MAX, 3, 0, 0, false), -50);
+        // and more than zero to its opponent
+        assert_eq!(bot.search(-i32::MAX, i32::MAX, 3, 1, 0, false), 50);
+
+        bot.set_contempt(-50);
+        assert_eq!(bot.search(-i32::MAX, i32::MAX, 3, 0, 0, false), 50);
+    }
+
+    #[test]
+    fn repetition_test1() {
+        let mut bot = bot_at("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        bot.set_contempt(30);
+        bot.search_path.push(bot.board.zobrist_hash);
+        for uci in ["g1f3", "g8f6", "f3g1"] {
+            play(&mut bot, uci);
+            assert!(!bot.is_repetition());
+        }
+        // back at the root position, one repetition inside the tree is a draw
+        play(&mut bot, "f6g8");
+        assert!(bot.is_repetition());
+        assert_eq!(bot.search(-i32::MAX, i32::MAX, 4, 4, 0, false), -30);
+        assert_eq!(bot.zw_search(1, 4, 4, false), -30);
+    }
+
+    #[test]
+    fn repetition_test2() {
+        // black can repeat with Ng8, the scores below depend on the path, even at depth 1
+        let mut bot = bot_at("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        bot.set_contempt(30);
+        bot.search_path.push(bot.board.zobrist_hash);
+        for uci in ["g1f3", "g8f6", "f3g1"] {
+            play(&mut bot, uci);
+        }
+        let hash = bot.board.zobrist_hash;
+        bot.search(-i32::MAX, i32::MAX, 1, 3, 0, false);
+        assert_eq!(bot.tt.probe_hash(hash, 1, 3, -i32::MAX, i32::MAX), None);
+
+        // without contempt a draw is worth the same from every root
+        bot.set_contempt(0);
+        let score = bot.search(-i32::MAX, i32::MAX, 1, 3, 0, false);
+        assert_eq!(
+            bot.tt
+                .probe_hash(hash, 1, 3, -i32::MAX, i32::MAX)
+                .map(|(score, _)| score),
+            Some(score)
+        );
+    }
+
+    #[test]
+    fn repetition_test3() {
+        // a score that only equals the draw score is no draw, it's stored
+        let mut bot = bot_at("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        bot.set_contempt(30);
+        let hash = bot.board.zobrist_hash;
+        let draw = bot.draw_score(2);
+        let draws = bot.draws;
+        bot.store_in_tt(NodeKind::Exact, hash, 1, 2, draw, Move(1), draws);
+        assert_eq!(
+            bot.tt.probe_hash(hash, 1, 2, -i32::MAX, i32::MAX),
+            Some((draw, Move(1)))
+        );
+
+        // after a draw only the move is updated, the score stays
+        bot.draw(3);
+        bot.store_in_tt(NodeKind::Exact, hash, 1, 2, draw - 5, Move(2), draws);
+        assert_eq!(
+            bot.tt.probe_hash(hash, 1, 2, -i32::MAX, i32::MAX),
+            Some((draw, Move(2)))
+        );
+    }
+
+    #[test]
+    fn gives_check_test1() {
+        let checks = |fen: &str| {
+            let mut bot = bot_at(fen);
+            let mut checks = vec![];
+            for m in bot.board.get_legal_moves().iter() {
+                bot.board.make_move(*m, true);
+                let in_check = bot.board.is_king_in_check(bot.board.get_turn());
+                let _ = bot.board.unmake_move();
+                assert_eq!(bot.gives_check(*m), in_check, "{} in {}", m.to_uci(), fen);
+                if in_check {
+                    checks.push(m.to_uci());
+                }
+            }
+            checks.sort();
+            checks
+        };
+        // the bishop uncovers the rook wherever it goes, the knight checks from c7 and d6
+        assert_eq!(
+            checks("4k3/8/8/1N6/8/4B3/3P4/K3R3 w - - 0 1"),
+            vec![
+                "b5c7", "b5d6", "e3a7", "e3b6", "e3c5", "e3d4", "e3f2", "e3f4", "e3g1", "e3g5",
+                "e3h6"
+            ]
+        );
+        // queen and rook promotions check along the rank, bishop and knight don't
+        assert_eq!(
+            checks("k7/5P2/8/8/8/8/8/7K w - - 0 1"),
+            vec!["f7f8q", "f7f8r"]
+        );
+        // castling, en passant and pawn checks are compared against making the move
+        checks("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        checks("8/8/8/2k5/3Pp3/8/8/4K2R b - d3 0 1");
+        checks("8/8/3k4/8/4P3/2N5/8/4K3 w - - 0 1");
+    }
+
+    #[test]
+    fn pruning_test1() {
+        // far behind, with nothing but a quiet king move: futility skips it unsearched
+        let mut bot = bot_at("k7/8/8/8/8/8/q7/7K w - - 0 1");
+        assert_eq!(bot.zw_search(1, 3, 0, false), 0);
+        assert_eq!(bot.iterations, 0);
+
+        // next to a mate score the static eval says nothing, the move is searched
+        let mut bot = bot_at("k7/8/8/8/8/8/q7/7K w - - 0 1");
+        bot.zw_search(MATE_THRESHOLD + 1, 3, 0, false);
+        assert!(bot.iterations > 0);
+    }
+
+    #[test]
+    fn pruning_test2() {
+        // three queens up but in check, reverse futility mustn't cut the node off
+        let mut bot = bot_at("4k3/8/8/8/8/8/8/QQQ1K2r w - - 0 1");
+        assert_eq!(bot.zw_search(0, 1, 0, false), 0);
+        assert!(bot.iterations > 0);
+    }
+
+    #[test]
+    fn pruning_test3() {
+        // a queen down, the only quiet move that isn't futile is the mate Rd8#
+        let mut bot = bot_at("6k1/5ppp/8/8/8/8/q4PPP/3R2K1 w - - 0 1");
+        assert_eq!(bot.zw_search(1, 1, 0, false), 1);
+    }
+
+    #[test]
+    fn pruning_test4() {
+        // only exd6 e.p. stalemates black, every quiet move is futile at this eval
+        let mut bot = bot_at("k5br/P4p1p/1K3P1P/3pP3/8/8/8/8 w - d6 0 2");
+        let static_eval = bot.evaluate();
+        assert!(static_eval + FUTILITY_MARGINS[2] < 0);
+        assert!(static_eval + RAZOR_MARGIN * 2 >= -1);
+
+        let en_passant = find_move(&bot, "e5d6");
+        assert!(!bot.is_quiet(en_passant));
+        assert_eq!(bot.zw_search(0, 2, 0, false), 0);
+    }
+
+    #[test]
+    fn quiescence_test1() {
+        // mated, in check quiescence can't stand pat
+        let mut bot = bot_at("3R2k1/5ppp/8/8/8/8/8/K7 b - - 0 1");
+        assert_eq!(
+            bot.search_all_captures(-i32::MAX, i32::MAX, false, 3, 1),
+            -MATE + 3
+        );
+
+        // every evasion loses the queen to the fork
+        let mut bot = bot_at("q3k3/2N5/8/8/8/8/7K/8 b - - 0 1");
+        let stand_pat = bot.evaluate();
+        assert!(bot.search_all_captures(-i32::MAX, i32::MAX, false, 0, 1) < stand_pat - 500);
+    }
+
+    #[test]
+    fn quiescence_test2() {
+        // Nc7+ forks king and queen, only found by the quiet checks of the first ply
+        let mut bot = bot_at("q3k3/8/8/3N4/8/8/7K/8 w - - 0 1");
+        let without_checks = bot.search_all_captures(-i32::MAX, i32::MAX, false, 0, 1);
+        let with_checks = bot.search_all_captures(-i32::MAX, i32::MAX, false, 0, 0);
+        assert_eq!(without_checks, bot.evaluate());
+        assert!(with_checks > without_checks + 500);
+    }
+
+    #[test]
+    fn quiescence_test3() {
+        // a quiet queen promotion is searched at any quiescence ply
+        let mut bot = bot_at("7k/8/8/8/8/8/1P5p/K7 b - - 0 1");
+        let stand_pat = bot.evaluate();
+        assert!(bot.search_all_captures(-i32::MAX, i32::MAX, false, 0, 3) > stand_pat + 500);
+    }
+
+    #[test]
+    fn quiescence_test4() {
+        // exd5 can't get anywhere near alpha, delta pruning skips it without searching
+        let mut bot = bot_at("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2");
+        let stand_pat = bot.evaluate();
+        bot.search_all_captures(stand_pat + 1000, stand_pat + 1001, false, 0, 1);
+        assert_eq!(bot.iterations, 0);
+
+        bot.search_all_captures(-i32::MAX, i32::MAX, false, 0, 1);
+        assert!(bot.iterations > 0);
+    }
+}
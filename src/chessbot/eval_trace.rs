@@ -0,0 +1,171 @@
+use bitschess::prelude::*;
+
+/// Every term `GiffiBot::evaluate` adds up, in the order they're printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvalTerm {
+    Material,
+    PawnTable,
+    KnightTable,
+    BishopTable,
+    RookTable,
+    QueenTable,
+    KingTable,
+    DoubledPawns,
+    PassedPawns,
+    PawnsTowardsKing,
+    /// Pushing the enemy king to the edge in endgames
+    KingEndgame,
+}
+
+impl EvalTerm {
+    pub const ALL: [EvalTerm; 11] = [
+        EvalTerm::Material,
+        EvalTerm::PawnTable,
+        EvalTerm::KnightTable,
+        EvalTerm::BishopTable,
+        EvalTerm::RookTable,
+        EvalTerm::QueenTable,
+        EvalTerm::KingTable,
+        EvalTerm::DoubledPawns,
+        EvalTerm::PassedPawns,
+        EvalTerm::PawnsTowardsKing,
+        EvalTerm::KingEndgame,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            EvalTerm::Material => "Material",
+            EvalTerm::PawnTable => "Pawn table",
+            EvalTerm::KnightTable => "Knight table",
+            EvalTerm::BishopTable => "Bishop table",
+            EvalTerm::RookTable => "Rook table",
+            EvalTerm::QueenTable => "Queen table",
+            EvalTerm::KingTable => "King table",
+            EvalTerm::DoubledPawns => "Doubled pawns",
+            EvalTerm::PassedPawns => "Passed pawns",
+            EvalTerm::PawnsTowardsKing => "Pawns towards king",
+            EvalTerm::KingEndgame => "King endgame",
+        }
+    }
+}
+
+/// Sees every term of the evaluation as it's added, from the piece owner's point of view.
+///
+/// The search evaluates with `NoTrace`, which compiles down to the untraced evaluation.
+pub trait EvalTracer {
+    fn term(&mut self, term: EvalTerm, color: PieceColor, value: i32);
+    /// Which phase the evaluation was made in, decides the king table.
+    fn phase(&mut self, _end_game: bool, _phase_material: i32) {}
+}
+
+pub struct NoTrace;
+
+impl EvalTracer for NoTrace {
+    #[inline(always)]
+    fn term(&mut self, _term: EvalTerm, _color: PieceColor, _value: i32) {}
+}
+
+/// Per term and per side breakdown of a static evaluation, printed by the `eval` command.
+#[derive(Debug, Clone, Default)]
+pub struct EvalTrace {
+    white: [i32; EvalTerm::ALL.len()],
+    black: [i32; EvalTerm::ALL.len()],
+    pub end_game: bool,
+    /// Bishop, rook and queen material of both sides
+    pub phase_material: i32,
+    /// The evaluation from the side to move's point of view, as the search sees it
+    pub eval: i32,
+    pub white_to_move: bool,
+}
+
+impl EvalTrace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn white(&self, term: EvalTerm) -> i32 {
+        self.white[term as usize]
+    }
+
+    pub fn black(&self, term: EvalTerm) -> i32 {
+        self.black[term as usize]
+    }
+
+    /// White's terms minus black's.
+    pub fn total(&self, term: EvalTerm) -> i32 {
+        self.white(term) - self.black(term)
+    }
+}
+
+impl EvalTracer for EvalTrace {
+    fn term(&mut self, term: EvalTerm, color: PieceColor, value: i32) {
+        if color == PieceColor::White {
+            self.white[term as usize] += value;
+        } else {
+            self.black[term as usize] += value;
+        }
+    }
+
+    fn phase(&mut self, end_game: bool, phase_material: i32) {
+        self.end_game = end_game;
+        self.phase_material = phase_material;
+    }
+}
+
+impl std::fmt::Display for EvalTrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<20}|{:>8} |{:>8} |{:>8}",
+            "Term", "White", "Black", "Total"
+        )?;
+        writeln!(f, "{:-<20}+{:-<9}+{:-<9}+{:-<9}", "", "", "", "")?;
+        for term in EvalTerm::ALL {
+            writeln!(
+                f,
+                "{:<20}|{:>8} |{:>8} |{:>8}",
+                term.name(),
+                self.white(term),
+                self.black(term),
+                self.total(term)
+            )?;
+        }
+        writeln!(f, "{:-<20}+{:-<9}+{:-<9}+{:-<9}", "", "", "", "")?;
+
+        let white_sum = EvalTerm::ALL
+            .iter()
+            .map(|term| self.white(*term))
+            .sum::<i32>();
+        let black_sum = EvalTerm::ALL
+            .iter()
+            .map(|term| self.black(*term))
+            .sum::<i32>();
+        writeln!(
+            f,
+            "{:<20}|{:>8} |{:>8} |{:>8}",
+            "Sum",
+            white_sum,
+            black_sum,
+            white_sum - black_sum
+        )?;
+        writeln!(f)?;
+
+        let phase = if self.end_game {
+            "endgame"
+        } else {
+            "middlegame"
+        };
+        writeln!(
+            f,
+            "Phase: {} (bishop, rook and queen material {}), {} king table",
+            phase, self.phase_material, phase
+        )?;
+        write!(
+            f,
+            "Evaluation: {} from white's side, {} for the side to move ({})",
+            white_sum - black_sum,
+            self.eval,
+            if self.white_to_move { "white" } else { "black" }
+        )
+    }
+}
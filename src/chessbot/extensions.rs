@@ -0,0 +1,176 @@
+use bitschess::prelude::*;
+use std::sync::atomic::Ordering;
+
+use super::transposition_table::NodeKind;
+use super::{GiffiBot, MATE_THRESHOLD, SEVENTH_RANK};
+
+/// Plies of extensions a path gets even in the shallow iterations.
+pub(super) const MIN_EXTENSION_BUDGET: i32 = 16;
+/// Singular extensions are tried from this depth on, the exclusion search is too costly below.
+const SINGULAR_MIN_DEPTH: i32 = 6;
+/// The TT entry may be this much shallower than the node and still be trusted.
+const SINGULAR_TT_DEPTH_MARGIN: i32 = 3;
+/// Centipawns per ply of depth the other moves have to stay below the TT score.
+const SINGULAR_MARGIN: i32 = 2;
+
+impl GiffiBot {
+    /// Plies to extend `chess_move` by, made from the current position.
+    ///
+    /// Every path gets `extension_budget` plies of extensions, `extension_count` is how many
+    /// the path to this node has used.
+    pub fn get_extension(&self, chess_move: Move, extension_count: u8, singular: bool) -> u8 {
+        if extension_count >= self.extension_budget {
+            return 0;
+        }
+
+        // check evasions
+        if self.board.is_king_in_check(self.board.turn) {
+            return 1;
+        }
+        if chess_move.get_flag() == MoveFlag::PromoteQueen {
+            return 1;
+        }
+        if singular || self.is_recapture(chess_move) || self.is_pawn_push_to_seventh(chess_move) {
+            return 1;
+        }
+
+        0
+    }
+
+    /// Captures back on the square the previous move captured on.
+    fn is_recapture(&self, chess_move: Move) -> bool {
+        let previous = match self.capture_path.last() {
+            Some(previous) if *previous != Move(0) => *previous,
+            _ => return false,
+        };
+        previous.get_to_idx() == chess_move.get_to_idx()
+            && self
+                .board
+                .get_piece(chess_move.get_to_idx())
+                .get_piece_type()
+                != PieceType::None
+    }
+
+    /// A passed pawn moving to the rank before promotion.
+    fn is_pawn_push_to_seventh(&self, chess_move: Move) -> bool {
+        if self
+            .board
+            .get_piece(chess_move.get_from_idx())
+            .get_piece_type()
+            != PieceType::Pawn
+        {
+            return false;
+        }
+        let color = self.board.get_turn();
+        let to = chess_move.get_to_idx() as i32;
+        (1u64 << to) & SEVENTH_RANK[color as usize] != 0 && self.is_passed_pawn(color, to)
+    }
+
+    /// The TT move, if a reduced depth search shows every other move falling clearly short of it.
+    pub(super) fn singular_move(
+        &mut self,
+        moves: &MoveContainer,
+        depth: i32,
+        ply_from_root: i32,
+        cancellable: bool,
+    ) -> Option<Move> {
+        if depth < SINGULAR_MIN_DEPTH {
+            return None;
+        }
+        let (tt_move, tt_score, tt_depth, kind) = self
+            .tt
+            .probe_entry(self.board.zobrist_hash, ply_from_root)?;
+        if tt_move == Move(0)
+            || kind == NodeKind::UpperBound
+            || tt_depth < depth - SINGULAR_TT_DEPTH_MARGIN
+            || tt_score.abs() >= MATE_THRESHOLD
+            || !moves.iter().any(|m| *m == tt_move)
+        {
+            return None;
+        }
+
+        // the exclusion search is off the PV, it mustn't follow or stop following it
+        let follow_pv = std::mem::replace(&mut self.follow_pv, false);
+        let singular_beta = tt_score - SINGULAR_MARGIN * depth;
+        let mut singular = true;
+        for m in moves.iter() {
+            if *m == tt_move {
+                continue;
+            }
+            self.iterations += 1;
+            self.make_search_move(*m);
+            let eval = -self.zw_search(
+                1 - singular_beta,
+                (depth - 1) / 2,
+                ply_from_root + 1,
+                cancellable,
+            );
+            self.unmake_search_move();
+            // a cancelled search returns 0, that's no score to compare
+            if eval >= singular_beta || self.search_cancelled.load(Ordering::Relaxed) {
+                singular = false;
+                break;
+            }
+        }
+        self.follow_pv = follow_pv;
+
+        singular.then_some(tt_move)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chessbot::test_util::{bot_at, find_move};
+
+    #[test]
+    fn extension_test1() {
+        let mut bot = bot_at("k2q4/8/4P3/3p4/4P3/8/8/K7 w - - 0 1");
+        bot.extension_budget = 4;
+
+        let push = find_move(&bot, "e6e7");
+        assert_eq!(bot.get_extension(push, 0, false), 1);
+        // the budget of the path is used up
+        assert_eq!(bot.get_extension(push, 4, false), 0);
+        assert_eq!(bot.get_extension(find_move(&bot, "a1b1"), 0, false), 0);
+        assert_eq!(bot.get_extension(find_move(&bot, "a1b1"), 0, true), 1);
+
+        bot.make_search_move(find_move(&bot, "e4d5"));
+        assert_eq!(bot.get_extension(find_move(&bot, "d8d5"), 0, false), 1);
+        assert_eq!(bot.get_extension(find_move(&bot, "d8d7"), 0, false), 0);
+    }
+
+    #[test]
+    fn singular_test1() {
+        // only Qxd5 keeps the queen, every other move is far below the TT score
+        let mut bot = bot_at("4k3/8/8/3q4/8/8/8/3QK3 w - - 0 1");
+        let capture = find_move(&bot, "d1d5");
+        let hash = bot.board.zobrist_hash;
+        bot.tt
+            .store_evaluation(NodeKind::Exact, hash, 6, 0, 900, capture);
+        let moves = bot.board.get_legal_moves();
+        assert_eq!(bot.singular_move(&moves, 6, 0, false), Some(capture));
+        // too shallow for the exclusion search
+        assert_eq!(bot.singular_move(&moves, 5, 0, false), None);
+
+        // the exclusion search was cancelled, its scores say nothing
+        bot.search_cancelled.store(true, Ordering::Relaxed);
+        assert_eq!(bot.singular_move(&moves, 6, 0, false), None);
+    }
+
+    #[test]
+    fn singular_test2() {
+        // plenty of moves reach a TT score this low, e4 isn't singular
+        let mut bot = bot_at("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let push = find_move(&bot, "e2e4");
+        let hash = bot.board.zobrist_hash;
+        bot.tt
+            .store_evaluation(NodeKind::Exact, hash, 6, 0, -500, push);
+        let moves = bot.board.get_legal_moves();
+        assert_eq!(bot.singular_move(&moves, 6, 0, false), None);
+        // an upper bound doesn't say the TT move is good at all
+        bot.tt
+            .store_evaluation(NodeKind::UpperBound, hash, 6, 0, 900, push);
+        assert_eq!(bot.singular_move(&moves, 6, 0, false), None);
+    }
+}
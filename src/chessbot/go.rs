@@ -1,66 +1,165 @@
 use std::collections::VecDeque;
 use std::sync::atomic::Ordering;
-use std::sync::Arc;
+use std::sync::{atomic::AtomicBool, Arc};
 use std::time::Duration;
 
+use super::extensions::MIN_EXTENSION_BUDGET;
+use super::root::RootMove;
+use super::search_info::{Score, SearchInfo, SearchResult};
 use super::GiffiBot;
 use bitschess::prelude::*;
 
-use super::{MATE, MATE_THRESHOLD};
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Every limit that is set is honored, the search stops at whichever is hit first.
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimits {
+    pub depth: Option<i32>,
+    pub movetime: Option<Duration>,
+    /// Checked against `GiffiBot::iterations`
+    pub nodes: Option<u64>,
+    /// Stop as soon as a mate in at most N moves is found
+    pub mate: Option<i32>,
+    /// Calculates until search_cancelled is set to true
+    pub infinite: bool,
+    /// Only these root moves are searched, empty searches every legal move
+    pub searchmoves: Vec<Move>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: i32) -> Self {
+        Self {
+            depth: Some(depth),
+            ..Default::default()
+        }
+    }
+
+    pub fn movetime(time: Duration) -> Self {
+        Self {
+            movetime: Some(time),
+            ..Default::default()
+        }
+    }
+
+    pub fn infinite() -> Self {
+        Self {
+            infinite: true,
+            ..Default::default()
+        }
+    }
+
+    /// No limits set, the search would never end on its own.
+    pub fn is_unbounded(&self) -> bool {
+        self.depth.is_none()
+            && self.movetime.is_none()
+            && self.nodes.is_none()
+            && self.mate.is_none()
+    }
+
+    fn max_depth(&self) -> i32 {
+        let mut max_depth = self.depth.unwrap_or(super::MAX_DEPTH);
+        if let Some(mate) = self.mate {
+            // a mate in N moves is at most 2N - 1 plies away
+            max_depth = max_depth.min((mate * 2 - 1).max(1));
+        }
+        max_depth.min(super::MAX_DEPTH)
+    }
+
+    /// A score the search can stop at early. With a `mate` limit only a mate for the side to
+    /// move in at most that many moves counts, without one any forced mate.
+    fn is_final(&self, score: i32) -> bool {
+        match (self.mate, Score::from_search(score)) {
+            (Some(mate), Score::Mate(moves)) => moves > 0 && moves <= mate,
+            (None, score) => score.is_mate(),
+            _ => false,
+        }
+    }
+}
 
 impl GiffiBot {
     /// Calculates until search_cancelled is set to true
     #[inline(always)]
-    pub fn go_infinite(&mut self) {
-        self.go_depth(super::MAX_DEPTH);
+    pub fn go_infinite(&mut self) -> SearchResult {
+        self.go(&SearchLimits::infinite())
+    }
+
+    #[inline(always)]
+    pub fn go_timed(&mut self, time: Duration) -> SearchResult {
+        self.go(&SearchLimits::movetime(time))
     }
 
     #[inline(always)]
-    pub fn go_timed(&mut self, time: Duration) {
+    pub fn go_depth(&mut self, depth: i32) -> SearchResult {
+        self.go(&SearchLimits::depth(depth))
+    }
+
+    pub fn go(&mut self, limits: &SearchLimits) -> SearchResult {
         const CHECK_INTERVAL: Duration = Duration::from_millis(10);
 
-        let copy_cancel = Arc::clone(&self.search_cancelled);
+        let search_done = Arc::new(AtomicBool::new(false));
+        let timer = limits.movetime.map(|time| {
+            let copy_cancel = Arc::clone(&self.search_cancelled);
+            let copy_done = Arc::clone(&search_done);
 
-        let handle = std::thread::spawn(move || {
-            let start = std::time::Instant::now();
+            std::thread::spawn(move || {
+                let start = std::time::Instant::now();
 
-            loop {
-                std::thread::sleep(CHECK_INTERVAL);
+                loop {
+                    std::thread::sleep(CHECK_INTERVAL);
 
-                // already set to true, before time ran out (most likely user manually called 'stop')
-                if copy_cancel.load(Ordering::Relaxed) {
-                    break;
-                }
+                    // already set to true, before time ran out (most likely user manually called 'stop')
+                    if copy_cancel.load(Ordering::Relaxed) || copy_done.load(Ordering::Relaxed) {
+                        break;
+                    }
 
-                // slept for the target amount
-                let slept_for = std::time::Instant::now() - start;
-                if slept_for >= time {
-                    copy_cancel.store(true, Ordering::Relaxed);
-                    break;
+                    // slept for the target amount
+                    let slept_for = std::time::Instant::now() - start;
+                    if slept_for >= time {
+                        copy_cancel.store(true, Ordering::Relaxed);
+                        break;
+                    }
                 }
-            }
+            })
         });
 
-        self.go_depth(super::MAX_DEPTH);
-        let _ = handle.join();
+        let limits = &self.skill.limit(limits);
+        self.node_limit = limits.nodes.unwrap_or(u64::MAX);
+        self.searchmoves = limits.searchmoves.clone();
+        let result = self.iterative_deepening(limits);
+
+        search_done.store(true, Ordering::Relaxed);
+        if let Some(handle) = timer {
+            let _ = handle.join();
+        }
+        result
     }
 
-    pub fn go_depth(&mut self, depth: i32) {
+    fn iterative_deepening(&mut self, limits: &SearchLimits) -> SearchResult {
+        self.reporter.begin(&self.board);
         self.iterations = 0;
         self.search_begin = std::time::Instant::now();
+        self.last_progress = self.search_begin;
         self.completed_depth = 0;
+        // the bot may be reused, the line of the previous position means nothing here
+        self.pv.clear();
+        self.search_path.clear();
+        self.search_path.push(self.board.zobrist_hash);
+        self.capture_path.clear();
+        self.tt.new_search();
+        self.init_root_moves();
         let mut best_completed_line = VecDeque::new();
+        let mut last_info = SearchInfo::default();
 
-        for depth in 1..=depth {
-            let mut line = VecDeque::new();
-            let perspective = if self.board.get_turn() == PieceColor::White {
-                1
-            } else {
-                -1
-            };
+        for depth in 1..=limits.max_depth() {
             let cancellable = depth > 1;
-            let score = self.search(-i32::MAX, i32::MAX, depth, 0, &mut line, 0, cancellable);
-            let score_with_perspective = score * perspective;
+            self.seldepth = 0;
+            // extensions can double the depth of a path, or add MIN_EXTENSION_BUDGET plies in
+            // the shallow iterations, without running past MAX_DEPTH
+            self.extension_budget = depth
+                .max(MIN_EXTENSION_BUDGET)
+                .min(super::MAX_DEPTH - depth)
+                .max(0) as u8;
+            let score = self.search_root(depth, cancellable);
 
             // Calculate at least one move
             if cancellable && self.search_cancelled.load(Ordering::Relaxed) {
@@ -68,59 +167,122 @@ impl GiffiBot {
             }
 
             // if search was cancelled, the line is going to be incomplete
-            best_completed_line = line;
+            best_completed_line = self
+                .root_moves
+                .first()
+                .map(|root_move| root_move.pv.iter().copied().collect())
+                .unwrap_or_default();
             self.pv = best_completed_line.clone();
             self.completed_depth = depth;
 
+            if self.pv.is_empty() {
+                continue;
+            }
+
             // Stats
-            let end = std::time::Instant::now();
-            let duration = end - self.search_begin;
-
-            // Break if mate or centipawn score
-            if let Some(chess_move) = self.pv.front() {
-                print!("info depth {} ", depth);
-                if score.abs() >= MATE_THRESHOLD {
-                    let plies = MATE - score.abs();
-                    let mate_in = (plies + 1) / 2;
-                    let mate_in = if score > 0 { mate_in } else { -mate_in };
-                    print!("score mate {} ", mate_in);
-                } else {
-                    print!("score cp {} ", score_with_perspective);
-                }
-                print!(
-                    "currmove {} nodes {} time {} nps {} ",
-                    chess_move.to_uci(),
-                    self.iterations,
-                    duration.as_millis(),
-                    (self.iterations as f32 / duration.as_secs_f32()) as i32
-                );
-
-                // The full calcualted line
-                print!("pv ");
-                for m in &self.pv {
-                    print!("{} ", m.to_uci());
-                }
-                println!();
+            let info = self.search_info(depth, score);
+            self.reporter.info(&info);
+            last_info = info;
 
-                // Forced mate, we can stop calculating
-                if score.abs() >= MATE_THRESHOLD {
-                    break;
-                }
+            // Forced mate, we can stop calculating
+            if limits.is_final(score) {
+                break;
+            }
+        }
+
+        if !self.skill.is_full_strength() && !best_completed_line.is_empty() {
+            if let Some(chosen) = self.pick_weakened_move() {
+                best_completed_line = chosen.pv.iter().copied().collect();
             }
         }
 
         // last resort: make sure we always have a move to play
         if best_completed_line.is_empty() {
-            if let Some(m) = self.board.get_legal_moves().get(0) {
+            if let Some(m) = self.searchmoves.first() {
+                best_completed_line.push_front(*m);
+            } else if let Some(m) = self.board.get_legal_moves().get(0) {
                 best_completed_line.push_front(m);
             }
         }
         self.pv = best_completed_line;
 
-        if let Some(chess_move) = self.pv.front() {
-            println!("bestmove {}", chess_move.to_uci());
-        } else {
-            println!("bestmove 0000");
+        self.reporter.bestmove(self.pv.front().copied());
+
+        // the totals of the whole search rather than of the last iteration
+        last_info.nodes = self.iterations;
+        last_info.time = self.search_begin.elapsed();
+        last_info.nps = Self::nodes_per_second(last_info.nodes, last_info.time);
+        SearchResult {
+            best_move: self.pv.front().copied(),
+            info: last_info,
+        }
+    }
+
+    /// Lets `Skill` pick one of the root moves by the exact scores of the last completed iteration.
+    fn pick_weakened_move(&mut self) -> Option<&RootMove> {
+        let scores = self
+            .root_moves
+            .iter()
+            .map(|root_move| root_move.score)
+            .collect::<Vec<i32>>();
+        let picked = self.skill.pick(&scores, &mut self.rng);
+        self.root_moves.get(picked)
+    }
+
+    #[inline(always)]
+    fn nodes_per_second(nodes: u64, time: Duration) -> u64 {
+        (nodes as f64 / time.as_secs_f64()) as u64
+    }
+
+    /// `pv` continued with the TT moves after it. Every move is checked to be legal, so a
+    /// hash collision can't put garbage in the output, and the line stops at a repetition.
+    fn display_pv(&self) -> Vec<Move> {
+        let mut board = self.board.clone();
+        let mut seen = vec![board.zobrist_hash];
+        let mut line = vec![];
+
+        let mut next = self.pv.iter().copied();
+        while line.len() < super::MAX_DEPTH as usize {
+            let chess_move = match next.next() {
+                Some(chess_move) => chess_move,
+                None => self.tt.get_entry_by_hash(board.zobrist_hash),
+            };
+            if chess_move == Move(0) || !board.get_legal_moves().iter().any(|m| *m == chess_move) {
+                break;
+            }
+            board.make_move(chess_move, true);
+            line.push(chess_move);
+            if seen.contains(&board.zobrist_hash) {
+                break;
+            }
+            seen.push(board.zobrist_hash);
+        }
+        line
+    }
+
+    /// Node count and speed in the middle of an iteration, at most once per `PROGRESS_INTERVAL`.
+    pub(super) fn report_progress(&mut self) {
+        if self.last_progress.elapsed() < PROGRESS_INTERVAL {
+            return;
+        }
+        self.last_progress = std::time::Instant::now();
+        let time = self.search_begin.elapsed();
+        let nps = Self::nodes_per_second(self.iterations, time);
+        let hashfull = self.tt.hashfull();
+        self.reporter.progress(self.iterations, nps, time, hashfull);
+    }
+
+    fn search_info(&self, depth: i32, score: i32) -> SearchInfo {
+        let time = self.search_begin.elapsed();
+        SearchInfo {
+            depth,
+            seldepth: self.seldepth,
+            score: Score::from_search(score),
+            nodes: self.iterations,
+            nps: Self::nodes_per_second(self.iterations, time),
+            time,
+            pv: self.display_pv(),
+            hashfull: self.tt.hashfull(),
         }
     }
 }
@@ -0,0 +1,146 @@
+use bitschess::prelude::*;
+
+use super::GiffiBot;
+
+/// Nodes shallower than this are searched as they are, a bad first move is cheap there.
+const INTERNAL_ITERATION_MIN_DEPTH: i32 = 4;
+/// Internal iterative deepening searches the node this much shallower first.
+const IID_REDUCTION: i32 = 2;
+
+/// What a PV node without a hash move does to find a good first move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InternalIteration {
+    /// Move ordering falls back to captures and killers
+    Off,
+    /// Internal iterative reduction: the node is searched a ply shallower, the next
+    /// iteration finds the hash move this one stores
+    #[default]
+    Reduction,
+    /// Internal iterative deepening: a shallower search of the node first, for its best move
+    Deepening,
+}
+
+impl InternalIteration {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Reduction => "reduction",
+            Self::Deepening => "deepening",
+        }
+    }
+}
+
+impl std::fmt::Display for InternalIteration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for InternalIteration {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "reduction" | "iir" => Ok(Self::Reduction),
+            "deepening" | "iid" => Ok(Self::Deepening),
+            _ => Err(format!(
+                "unknown internal iteration '{}', expected off, reduction or deepening",
+                s
+            )),
+        }
+    }
+}
+
+impl GiffiBot {
+    pub fn set_internal_iteration(&mut self, internal_iteration: InternalIteration) {
+        self.internal_iteration = internal_iteration;
+    }
+
+    /// The depth to search a PV node to, after making sure it has a move to try first.
+    pub(super) fn internal_iteration(
+        &mut self,
+        alpha: i32,
+        beta: i32,
+        depth: i32,
+        ply_from_root: i32,
+        extension_count: u8,
+        cancellable: bool,
+    ) -> i32 {
+        // the previous PV is as good as a hash move
+        if depth < INTERNAL_ITERATION_MIN_DEPTH
+            || self.follow_pv
+            || self.tt.get_entry_by_hash(self.board.zobrist_hash) != Move(0)
+        {
+            return depth;
+        }
+
+        match self.internal_iteration {
+            InternalIteration::Off => depth,
+            InternalIteration::Reduction => depth - 1,
+            InternalIteration::Deepening => {
+                self.search(
+                    alpha,
+                    beta,
+                    depth - IID_REDUCTION,
+                    ply_from_root,
+                    extension_count,
+                    cancellable,
+                );
+                depth
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::chessbot::test_util::bot_at;
+
+    #[test]
+    fn internal_iteration_test1() {
+        let mut bot = bot_at("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let hash = bot.board.zobrist_hash;
+
+        bot.set_internal_iteration(InternalIteration::Off);
+        assert_eq!(
+            bot.internal_iteration(-i32::MAX, i32::MAX, 6, 0, 0, false),
+            6
+        );
+
+        bot.set_internal_iteration(InternalIteration::Reduction);
+        assert_eq!(
+            bot.internal_iteration(-i32::MAX, i32::MAX, 6, 0, 0, false),
+            5
+        );
+        assert_eq!(
+            bot.internal_iteration(-i32::MAX, i32::MAX, 3, 0, 0, false),
+            3
+        );
+        // a node on the previous PV has its move
+        bot.follow_pv = true;
+        assert_eq!(
+            bot.internal_iteration(-i32::MAX, i32::MAX, 6, 0, 0, false),
+            6
+        );
+        bot.follow_pv = false;
+
+        // deepening keeps the depth and leaves a hash move behind
+        bot.set_internal_iteration(InternalIteration::Deepening);
+        assert_eq!(bot.tt.get_entry_by_hash(hash), Move(0));
+        assert_eq!(
+            bot.internal_iteration(-i32::MAX, i32::MAX, 6, 0, 0, false),
+            6
+        );
+        assert!(bot.iterations > 0);
+        assert_ne!(bot.tt.get_entry_by_hash(hash), Move(0));
+
+        // with a hash move there's nothing left to do
+        bot.set_internal_iteration(InternalIteration::Reduction);
+        assert_eq!(
+            bot.internal_iteration(-i32::MAX, i32::MAX, 6, 0, 0, false),
+            6
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use bitschess::Move;
+
+use super::MAX_DEPTH;
+
+const MAX_PLY: usize = MAX_DEPTH as usize;
+
+/// Triangular PV table, row `ply` holds the best line found from the node at that ply.
+///
+/// A node clears its row on entry and, when a move raises alpha, copies the row of the
+/// ply below behind the move. Lines are only ever built from searched moves.
+#[derive(Debug)]
+pub(crate) struct PvTable {
+    moves: Box<[[Move; MAX_PLY]; MAX_PLY]>,
+    length: [usize; MAX_PLY],
+}
+
+impl PvTable {
+    pub fn new() -> Self {
+        Self {
+            moves: Box::new([[Move(0); MAX_PLY]; MAX_PLY]),
+            length: [0; MAX_PLY],
+        }
+    }
+
+    pub fn clear(&mut self, ply: i32) {
+        if let Some(length) = self.length.get_mut(ply as usize) {
+            *length = 0;
+        }
+    }
+
+    /// `chess_move` followed by the line of the ply below.
+    pub fn update(&mut self, ply: i32, chess_move: Move) {
+        let ply = ply as usize;
+        if ply >= MAX_PLY {
+            return;
+        }
+        let child_length = if ply + 1 < MAX_PLY {
+            self.length[ply + 1].min(MAX_PLY - 1)
+        } else {
+            0
+        };
+
+        let (upper, lower) = self.moves.split_at_mut(ply + 1);
+        upper[ply][0] = chess_move;
+        if child_length > 0 {
+            upper[ply][1..=child_length].copy_from_slice(&lower[0][..child_length]);
+        }
+        self.length[ply] = child_length + 1;
+    }
+
+    pub fn line(&self, ply: i32) -> &[Move] {
+        match self.length.get(ply as usize) {
+            Some(length) => &self.moves[ply as usize][..*length],
+            None => &[],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pv_table_test1() {
+        let mut table = PvTable::new();
+        table.clear(2);
+        table.update(2, Move(3));
+        table.clear(1);
+        table.update(1, Move(2));
+        table.clear(0);
+        table.update(0, Move(1));
+        assert_eq!(table.line(0), &[Move(1), Move(2), Move(3)]);
+
+        // a new line at ply 1 doesn't touch the finished line above it
+        table.clear(2);
+        table.clear(1);
+        table.update(1, Move(4));
+        assert_eq!(table.line(1), &[Move(4)]);
+        assert_eq!(table.line(0), &[Move(1), Move(2), Move(3)]);
+    }
+}
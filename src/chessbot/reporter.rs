@@ -0,0 +1,186 @@
+use std::sync::{mpsc::Sender, Arc, Mutex};
+use std::time::Duration;
+
+use bitschess::prelude::*;
+
+use super::search_info::{Score, SearchInfo};
+
+/// Receives everything a search has to say, instead of the search printing it.
+pub trait SearchReporter: Send {
+    /// Called once before the search starts.
+    fn begin(&mut self, _board: &ChessBoard) {}
+    /// Called with every completed iteration.
+    fn info(&mut self, info: &SearchInfo);
+    /// The root move about to be searched, `number` counts from 1.
+    fn currmove(&mut self, _depth: i32, _chess_move: Move, _number: usize) {}
+    /// Called about once a second while an iteration runs, with the totals so far.
+    fn progress(&mut self, _nodes: u64, _nps: u64, _time: Duration, _hashfull: u32) {}
+    /// Called exactly once at the end of every search.
+    fn bestmove(&mut self, best_move: Option<Move>);
+    /// Free-form text, `info string` in UCI.
+    fn message(&mut self, _message: &str) {}
+}
+
+impl std::fmt::Debug for dyn SearchReporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SearchReporter")
+    }
+}
+
+/// Prints `info` and `bestmove` lines to stdout.
+#[derive(Debug)]
+pub struct UciReporter {
+    perspective: i32,
+}
+
+impl UciReporter {
+    pub fn new() -> Self {
+        Self { perspective: 1 }
+    }
+}
+
+impl Default for UciReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SearchReporter for UciReporter {
+    fn begin(&mut self, board: &ChessBoard) {
+        // centipawns are printed from white's point of view
+        self.perspective = if board.get_turn() == PieceColor::White {
+            1
+        } else {
+            -1
+        };
+    }
+
+    fn info(&mut self, info: &SearchInfo) {
+        let mut line = format!("info depth {} seldepth {} ", info.depth, info.seldepth);
+        match info.score {
+            Score::Mate(mate_in) => line.push_str(&format!("score mate {} ", mate_in)),
+            Score::Centipawns(cp) => line.push_str(&format!("score cp {} ", cp * self.perspective)),
+        }
+        line.push_str(&format!(
+            "nodes {} time {} nps {} hashfull {} ",
+            info.nodes,
+            info.time.as_millis(),
+            info.nps,
+            info.hashfull
+        ));
+
+        // The full calcualted line
+        line.push_str("pv ");
+        for m in &info.pv {
+            line.push_str(&m.to_uci());
+            line.push(' ');
+        }
+        println!("{}", line);
+    }
+
+    fn currmove(&mut self, depth: i32, chess_move: Move, number: usize) {
+        println!(
+            "info depth {} currmove {} currmovenumber {}",
+            depth,
+            chess_move.to_uci(),
+            number
+        );
+    }
+
+    fn progress(&mut self, nodes: u64, nps: u64, time: Duration, hashfull: u32) {
+        println!(
+            "info nodes {} time {} nps {} hashfull {}",
+            nodes,
+            time.as_millis(),
+            nps,
+            hashfull
+        );
+    }
+
+    fn bestmove(&mut self, best_move: Option<Move>) {
+        if let Some(chess_move) = best_move {
+            println!("bestmove {}", chess_move.to_uci());
+        } else {
+            println!("bestmove 0000");
+        }
+    }
+
+    fn message(&mut self, message: &str) {
+        println!("info string {}", message);
+    }
+}
+
+/// Discards everything, the result is still returned by `GiffiBot::go`.
+#[derive(Debug, Default)]
+pub struct SilentReporter;
+
+impl SearchReporter for SilentReporter {
+    fn info(&mut self, _info: &SearchInfo) {}
+    fn bestmove(&mut self, _best_move: Option<Move>) {}
+}
+
+/// Stores everything reported. Clones share the same storage, so keep one to read it back.
+#[derive(Debug, Clone, Default)]
+pub struct CollectingReporter {
+    infos: Arc<Mutex<Vec<SearchInfo>>>,
+    best_moves: Arc<Mutex<Vec<Option<Move>>>>,
+    messages: Arc<Mutex<Vec<String>>>,
+}
+
+impl CollectingReporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn infos(&self) -> Vec<SearchInfo> {
+        self.infos.lock().expect("not poisoned").clone()
+    }
+
+    pub fn best_moves(&self) -> Vec<Option<Move>> {
+        self.best_moves.lock().expect("not poisoned").clone()
+    }
+
+    pub fn messages(&self) -> Vec<String> {
+        self.messages.lock().expect("not poisoned").clone()
+    }
+}
+
+impl SearchReporter for CollectingReporter {
+    fn info(&mut self, info: &SearchInfo) {
+        self.infos.lock().expect("not poisoned").push(info.clone());
+    }
+
+    fn bestmove(&mut self, best_move: Option<Move>) {
+        self.best_moves
+            .lock()
+            .expect("not poisoned")
+            .push(best_move);
+    }
+
+    fn message(&mut self, message: &str) {
+        self.messages
+            .lock()
+            .expect("not poisoned")
+            .push(message.to_string());
+    }
+}
+
+/// Forwards every completed iteration to a closure.
+pub struct CallbackReporter<F: FnMut(&SearchInfo) + Send>(pub F);
+
+impl<F: FnMut(&SearchInfo) + Send> SearchReporter for CallbackReporter<F> {
+    fn info(&mut self, info: &SearchInfo) {
+        (self.0)(info);
+    }
+
+    fn bestmove(&mut self, _best_move: Option<Move>) {}
+}
+
+impl CallbackReporter<Box<dyn FnMut(&SearchInfo) + Send>> {
+    pub fn from_sender(sender: Sender<SearchInfo>) -> Self {
+        Self(Box::new(move |info| {
+            // receiver hanging up isn't a reason to stop searching
+            let _ = sender.send(info.clone());
+        }))
+    }
+}
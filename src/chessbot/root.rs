@@ -0,0 +1,169 @@
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use bitschess::prelude::*;
+
+use super::transposition_table::NodeKind;
+use super::{GiffiBot, MATE};
+
+/// `info currmove` lines are only printed once the search has run this long.
+pub const DEFAULT_CURRMOVE_DELAY: Duration = Duration::from_millis(3000);
+
+/// A legal move at the root and what the iterations so far found out about it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RootMove {
+    pub chess_move: Move,
+    /// `-i32::MAX` if the move failed low, only the best move has an exact score unless
+    /// the skill is weakened
+    pub score: i32,
+    /// The last exact score from an earlier iteration, `-i32::MAX` until the move had one
+    pub previous_score: i32,
+    /// Nodes spent on this move in the last iteration
+    pub nodes: u64,
+    /// Starts with `chess_move`
+    pub pv: Vec<Move>,
+}
+
+impl RootMove {
+    pub fn new(chess_move: Move) -> Self {
+        Self {
+            chess_move,
+            score: -i32::MAX,
+            previous_score: -i32::MAX,
+            nodes: 0,
+            pv: vec![chess_move],
+        }
+    }
+}
+
+impl GiffiBot {
+    /// The root moves of the last search, best first.
+    pub fn root_moves(&self) -> &[RootMove] {
+        &self.root_moves
+    }
+
+    /// How long the search runs before it starts printing `info currmove`.
+    pub fn set_currmove_delay(&mut self, delay: Duration) {
+        self.currmove_delay = delay;
+    }
+
+    /// Collects the legal root moves, restricted by `searchmoves`, in move ordering order.
+    pub(super) fn init_root_moves(&mut self) {
+        let mut moves = self.board.get_legal_moves();
+        self.follow_pv = false;
+        self.order_moves(&mut moves, 0);
+        self.root_moves = moves
+            .iter()
+            .filter(|m| self.searchmoves.is_empty() || self.searchmoves.contains(m))
+            .map(|m| RootMove::new(*m))
+            .collect();
+    }
+
+    /// Searches every root move and sorts them for the next iteration, by score and then by
+    /// the size of their subtree, so moves that almost worked are tried before the rest.
+    ///
+    /// A weakened `Skill` picks among all the root moves, so then every move gets a full
+    /// window and an exact score, like a MultiPV search.
+    pub(super) fn search_root(&mut self, depth: i32, cancellable: bool) -> i32 {
+        if self.root_moves.is_empty() {
+            if self.board.is_king_in_check(self.board.get_turn()) {
+                return -MATE;
+            }
+            return self.draw_score(0);
+        }
+
+        // a cancelled iteration leaves the list as the last completed one sorted it
+        let completed = self.root_moves.clone();
+        let draws_before = self.draws;
+
+        let exact_scores = !self.skill.is_full_strength();
+        let beta = i32::MAX;
+        let mut alpha = -i32::MAX;
+        let mut best_move = Move(0);
+        for i in 0..self.root_moves.len() {
+            let m = self.root_moves[i].chess_move;
+            if self.search_begin.elapsed() >= self.currmove_delay {
+                self.reporter.currmove(depth, m, i + 1);
+            }
+
+            let extension = self.get_extension(m, 0, false);
+            let nodes_before = self.iterations;
+            self.iterations += 1;
+            // the best move of the last iteration leads along its PV
+            self.follow_pv = i == 0 && self.pv.front() == Some(&m);
+            self.make_search_move(m);
+            let mut eval;
+            if i == 0 || exact_scores {
+                let window_alpha = if exact_scores { -i32::MAX } else { alpha };
+                eval = -self.search(
+                    -beta,
+                    -window_alpha,
+                    depth - 1 + (extension as i32),
+                    1,
+                    extension,
+                    cancellable,
+                );
+            } else {
+                eval = -self.zw_search(-alpha, depth - 1, 1, cancellable);
+                if eval > alpha {
+                    eval = -self.search(
+                        -beta,
+                        -alpha,
+                        depth - 1 + (extension as i32),
+                        1,
+                        extension,
+                        cancellable,
+                    );
+                }
+            }
+            self.unmake_search_move();
+            self.follow_pv = false;
+
+            if cancellable && self.search_cancelled.load(Ordering::Relaxed) {
+                self.root_moves = completed;
+                return 0;
+            }
+
+            let root_move = &mut self.root_moves[i];
+            root_move.nodes = self.iterations - nodes_before;
+            if root_move.score != -i32::MAX {
+                root_move.previous_score = root_move.score;
+            }
+            if eval > alpha {
+                alpha = eval;
+                best_move = m;
+                root_move.score = eval;
+                root_move.pv = std::iter::once(m)
+                    .chain(self.pv_table.line(1).iter().copied())
+                    .collect();
+            } else if exact_scores {
+                root_move.score = eval;
+                root_move.pv = std::iter::once(m)
+                    .chain(self.pv_table.line(1).iter().copied())
+                    .collect();
+            } else {
+                root_move.score = -i32::MAX;
+                root_move.pv.truncate(1);
+            }
+        }
+
+        // fail-lows have no score to tell them apart, the one that took longest to refute goes first
+        self.root_moves
+            .sort_by(|a, b| b.score.cmp(&a.score).then(b.nodes.cmp(&a.nodes)));
+
+        // the result of a restricted root doesn't hold for the position, keep it out of the TT
+        if self.searchmoves.is_empty() {
+            let hash = self.board.zobrist_hash;
+            self.store_in_tt(
+                NodeKind::Exact,
+                hash,
+                depth,
+                0,
+                alpha,
+                best_move,
+                draws_before,
+            );
+        }
+        alpha
+    }
+}
@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use bitschess::Move;
+
+use super::{MATE, MATE_THRESHOLD};
+
+/// Search score from the side to move's perspective.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    Centipawns(i32),
+    /// Mate in N moves, negative if the side to move is getting mated
+    Mate(i32),
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Self::Centipawns(0)
+    }
+}
+
+impl Score {
+    pub(crate) fn from_search(score: i32) -> Self {
+        if score.abs() < MATE_THRESHOLD {
+            return Self::Centipawns(score);
+        }
+        let plies = MATE - score.abs();
+        let mate_in = (plies + 1) / 2;
+        Self::Mate(if score > 0 { mate_in } else { -mate_in })
+    }
+
+    /// Back to the internal representation, `Mate(0)` is the side to move being mated.
+    pub(crate) fn to_search(self) -> i32 {
+        match self {
+            Self::Centipawns(cp) => cp,
+            Self::Mate(n) if n > 0 => MATE - (n * 2 - 1),
+            Self::Mate(n) => -MATE - n * 2,
+        }
+    }
+
+    pub fn is_mate(self) -> bool {
+        matches!(self, Self::Mate(_))
+    }
+}
+
+/// Stats of one completed iteration.
+#[derive(Debug, Clone, Default)]
+pub struct SearchInfo {
+    pub depth: i32,
+    /// Highest ply reached
+    pub seldepth: i32,
+    pub score: Score,
+    pub nodes: u64,
+    pub nps: u64,
+    pub time: Duration,
+    pub pv: Vec<Move>,
+    /// Transposition table usage in permille
+    pub hashfull: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchResult {
+    /// None only if the position has no legal moves
+    pub best_move: Option<Move>,
+    /// Info of the last completed iteration
+    pub info: SearchInfo,
+}
@@ -0,0 +1,175 @@
+use super::go::SearchLimits;
+use super::MATE_THRESHOLD;
+
+pub const MAX_SKILL_LEVEL: i32 = 20;
+pub const MIN_ELO: i32 = 600;
+pub const MAX_ELO: i32 = 2000;
+
+/// Moves evaluating this much worse than the best move are never picked, even beginners see those.
+const MAX_LOSS: i32 = 1000;
+
+/// How weakened the bot plays, `MAX_SKILL_LEVEL` is full strength.
+///
+/// The weakened levels cap the search depth and node count and pick root moves
+/// randomly, weighted by how much worse they evaluate than the best move.
+/// The Elo mapping is approximate, `testing/calibrate_strength.py` measures it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Skill {
+    level: i32,
+}
+
+impl Default for Skill {
+    fn default() -> Self {
+        Self::from_level(MAX_SKILL_LEVEL)
+    }
+}
+
+impl Skill {
+    pub fn from_level(level: i32) -> Self {
+        Self {
+            level: level.clamp(0, MAX_SKILL_LEVEL),
+        }
+    }
+
+    pub fn from_elo(elo: i32) -> Self {
+        let elo = elo.clamp(MIN_ELO, MAX_ELO);
+        // linear, MIN_ELO is level 0 and MAX_ELO the strongest weakened level
+        let level = (elo - MIN_ELO) * (MAX_SKILL_LEVEL - 1) / (MAX_ELO - MIN_ELO);
+        Self::from_level(level)
+    }
+
+    pub const fn level(self) -> i32 {
+        self.level
+    }
+
+    pub const fn is_full_strength(self) -> bool {
+        self.level >= MAX_SKILL_LEVEL
+    }
+
+    const fn depth_limit(self) -> i32 {
+        1 + self.level / 2
+    }
+
+    const fn node_limit(self) -> u64 {
+        1000 << (self.level / 2)
+    }
+
+    /// Centipawn loss that makes a move e (2.718) times less likely to be picked.
+    const fn temperature(self) -> i32 {
+        (MAX_SKILL_LEVEL - self.level) * 12
+    }
+
+    /// `limits` tightened to the depth and node caps of this level.
+    pub(crate) fn limit(self, limits: &SearchLimits) -> SearchLimits {
+        let mut limits = limits.clone();
+        if self.is_full_strength() {
+            return limits;
+        }
+        limits.depth = Some(
+            limits
+                .depth
+                .map_or(self.depth_limit(), |depth| depth.min(self.depth_limit())),
+        );
+        limits.nodes = Some(
+            limits
+                .nodes
+                .map_or(self.node_limit(), |nodes| nodes.min(self.node_limit())),
+        );
+        limits
+    }
+
+    /// Index of the move to play out of `scores`, chosen randomly and weighted by evaluation.
+    pub(crate) fn pick(self, scores: &[i32], rng: &mut Rng) -> usize {
+        let best = match scores.iter().max() {
+            Some(best) => *best,
+            None => return 0,
+        };
+        // never give up a forced mate, or throw away the only move not getting mated
+        if best.abs() >= MATE_THRESHOLD {
+            return scores.iter().position(|score| *score == best).unwrap_or(0);
+        }
+
+        let temperature = self.temperature().max(1) as f64;
+        let weights = scores
+            .iter()
+            .map(|score| {
+                let loss = best - score;
+                if loss > MAX_LOSS {
+                    return 0.0;
+                }
+                (-(loss as f64) / temperature).exp()
+            })
+            .collect::<Vec<f64>>();
+
+        let mut target = rng.next_f64() * weights.iter().sum::<f64>();
+        for (i, weight) in weights.iter().enumerate() {
+            if target < *weight {
+                return i;
+            }
+            target -= weight;
+        }
+        // only reachable through rounding errors
+        scores.iter().position(|score| *score == best).unwrap_or(0)
+    }
+}
+
+/// xorshift64*, plenty for picking moves.
+#[derive(Debug, Clone)]
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn from_time() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64);
+        Self(nanos | 1)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// In [0, 1)
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn skill_pick_test1() {
+        let mut rng = Rng(0x1234_5678);
+        let full = Skill::from_level(MAX_SKILL_LEVEL);
+        let weakest = Skill::from_level(0);
+
+        // full strength always has zero temperature left, only the best move gets picked
+        for _ in 0..100 {
+            assert_eq!(full.pick(&[10, 50, -20], &mut rng), 1);
+        }
+
+        // a forced mate is never thrown away
+        for _ in 0..100 {
+            assert_eq!(weakest.pick(&[10, MATE_THRESHOLD + 5, 30], &mut rng), 1);
+        }
+
+        // weak levels do play worse moves, but not hopeless ones
+        let picks = (0..1000)
+            .map(|_| weakest.pick(&[0, -40, -2000], &mut rng))
+            .collect::<Vec<usize>>();
+        assert!(picks.contains(&1));
+        assert!(!picks.contains(&2));
+    }
+
+    #[test]
+    fn skill_from_elo_test1() {
+        assert_eq!(Skill::from_elo(0).level(), 0);
+        assert_eq!(Skill::from_elo(MAX_ELO).level(), MAX_SKILL_LEVEL - 1);
+        assert!(Skill::from_elo(1500).level() > Skill::from_elo(1000).level());
+    }
+}
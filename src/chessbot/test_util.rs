@@ -0,0 +1,22 @@
+use std::sync::{atomic::AtomicBool, Arc};
+
+use bitschess::prelude::*;
+
+use super::GiffiBot;
+
+/// A bot on `fen` that nobody else can stop.
+pub(super) fn bot_at(fen: &str) -> GiffiBot {
+    let mut board = ChessBoard::new();
+    board.parse_fen(fen).expect("valid fen");
+    GiffiBot::new(board, Arc::new(AtomicBool::new(false)))
+}
+
+/// The legal move of the bot's position written as `uci`.
+pub(super) fn find_move(bot: &GiffiBot, uci: &str) -> Move {
+    bot.board
+        .get_legal_moves()
+        .iter()
+        .copied()
+        .find(|m| m.to_uci() == uci)
+        .expect("legal move")
+}
@@ -1,11 +1,28 @@
 use bitschess::Move;
 use std::cell::Cell;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use super::MATE_THRESHOLD;
 
 const SIZE_IN_MB: u64 = 64;
-const ENTRY_COUNT: u64 =
-    (1024 * 1024 * SIZE_IN_MB) / (std::mem::size_of::<TranspositionEntry>() as u64);
+/// 12 byte entries, five fill a 64 byte cache line.
+const BUCKET_SIZE: usize = 5;
+const BUCKET_COUNT: u64 = (1024 * 1024 * SIZE_IN_MB) / (std::mem::size_of::<Bucket>() as u64);
+/// Start of every saved table.
+const FILE_MAGIC: &[u8; 8] = b"GIFFITT\0";
+/// Bumped whenever the file layout changes.
+const FILE_FORMAT_VERSION: u32 = 1;
+
+/// Plies of depth an entry is worth less for every search it is older, when picking one to replace.
+const AGE_WEIGHT: i32 = 8;
+
+/// The low 2 bits of `TranspositionEntry::flags`, the generation is in the other 6.
+const KIND_MASK: u8 = 0b11;
+const GENERATION_MASK: u8 = !KIND_MASK;
+const GENERATION_STEP: u8 = KIND_MASK + 1;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum NodeKind {
     /// The stored evaluation can not be lower (score >= beta)
@@ -16,108 +33,512 @@ pub enum NodeKind {
     UpperBound,
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Packed into 12 bytes.
+///
+/// The bucket index already comes from the low bits of the hash, the upper 32 bits
+/// stored in `key` verify the position. Depth 0 marks an empty entry, stored depths
+/// are always at least 1.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
 struct TranspositionEntry {
-    zobrist_hash: u64,
-    kind: NodeKind,
-    score: i32,
-    depth: i32,
-    best_move: Move,
+    key: u32,
+    best_move: u16,
+    score: i16,
+    depth: u8,
+    /// `NodeKind` and the generation of the search that stored the entry
+    flags: u8,
 }
 
-impl Default for TranspositionEntry {
-    fn default() -> Self {
-        TranspositionEntry {
-            zobrist_hash: 0,
-            kind: NodeKind::Exact,
-            score: 0,
-            depth: 0,
-            best_move: Move(0),
+impl TranspositionEntry {
+    fn new(
+        hash: u64,
+        kind: NodeKind,
+        generation: u8,
+        score: i32,
+        depth: i32,
+        best_move: Move,
+    ) -> Self {
+        Self {
+            key: Self::key_of(hash),
+            best_move: best_move.0,
+            // only bounds of infinite windows get clamped, real scores fit
+            score: score.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+            depth: depth.clamp(1, u8::MAX as i32) as u8,
+            flags: (generation & GENERATION_MASK) | kind as u8,
+        }
+    }
+
+    #[inline(always)]
+    const fn key_of(hash: u64) -> u32 {
+        (hash >> 32) as u32
+    }
+
+    #[inline(always)]
+    fn matches(&self, hash: u64) -> bool {
+        !self.is_empty() && self.key == Self::key_of(hash)
+    }
+
+    #[inline(always)]
+    fn is_empty(&self) -> bool {
+        self.depth == 0
+    }
+
+    #[inline(always)]
+    fn kind(&self) -> NodeKind {
+        match self.flags & KIND_MASK {
+            0 => NodeKind::LowerBound,
+            1 => NodeKind::Exact,
+            _ => NodeKind::UpperBound,
+        }
+    }
+
+    #[inline(always)]
+    fn generation(&self) -> u8 {
+        self.flags & GENERATION_MASK
+    }
+
+    #[inline(always)]
+    fn score(&self) -> i32 {
+        self.score as i32
+    }
+
+    #[inline(always)]
+    fn depth(&self) -> i32 {
+        self.depth as i32
+    }
+
+    #[inline(always)]
+    fn best_move(&self) -> Move {
+        Move(self.best_move)
+    }
+
+    fn to_bytes(self) -> [u8; 10] {
+        let mut bytes = [0; 10];
+        bytes[0..4].copy_from_slice(&self.key.to_le_bytes());
+        bytes[4..6].copy_from_slice(&self.best_move.to_le_bytes());
+        bytes[6..8].copy_from_slice(&self.score.to_le_bytes());
+        bytes[8] = self.depth;
+        bytes[9] = self.flags;
+        bytes
+    }
+
+    fn from_bytes(bytes: [u8; 10]) -> Self {
+        Self {
+            key: u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            best_move: u16::from_le_bytes([bytes[4], bytes[5]]),
+            score: i16::from_le_bytes([bytes[6], bytes[7]]),
+            depth: bytes[8],
+            flags: bytes[9],
         }
     }
 }
 
-impl TranspositionEntry {
-    fn new(hash: u64, kind: NodeKind, score: i32, depth: i32, best_move: Move) -> Self {
+fn read_u32(reader: &mut impl Read) -> std::io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+#[derive(Debug)]
+pub enum TableFileError {
+    Io(std::io::Error),
+    NotATableFile,
+    /// Saved with a different layout, table size or engine version
+    Incompatible(String),
+}
+
+impl std::fmt::Display for TableFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{}", error),
+            Self::NotATableFile => write!(f, "not a saved hash table"),
+            Self::Incompatible(reason) => write!(f, "incompatible hash table, {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for TableFileError {}
+
+impl From<std::io::Error> for TableFileError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Written in front of the entries, everything has to match for a file to be loaded.
+#[derive(Debug, PartialEq, Eq)]
+struct TableFileHeader {
+    format_version: u32,
+    entry_size: u32,
+    bucket_size: u32,
+    bucket_count: u64,
+    engine_version: String,
+}
+
+impl TableFileHeader {
+    fn current() -> Self {
         Self {
-            zobrist_hash: hash,
-            kind,
-            score,
-            depth,
-            best_move,
+            format_version: FILE_FORMAT_VERSION,
+            entry_size: std::mem::size_of::<TranspositionEntry>() as u32,
+            bucket_size: BUCKET_SIZE as u32,
+            bucket_count: BUCKET_COUNT,
+            engine_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    fn write(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writer.write_all(FILE_MAGIC)?;
+        writer.write_all(&self.format_version.to_le_bytes())?;
+        writer.write_all(&self.entry_size.to_le_bytes())?;
+        writer.write_all(&self.bucket_size.to_le_bytes())?;
+        writer.write_all(&self.bucket_count.to_le_bytes())?;
+        writer.write_all(&(self.engine_version.len() as u16).to_le_bytes())?;
+        writer.write_all(self.engine_version.as_bytes())
+    }
+
+    fn read(reader: &mut impl Read) -> Result<Self, TableFileError> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != FILE_MAGIC {
+            return Err(TableFileError::NotATableFile);
         }
+
+        let format_version = read_u32(reader)?;
+        let entry_size = read_u32(reader)?;
+        let bucket_size = read_u32(reader)?;
+
+        let mut u64_bytes = [0; 8];
+        reader.read_exact(&mut u64_bytes)?;
+        let bucket_count = u64::from_le_bytes(u64_bytes);
+
+        let mut len_bytes = [0; 2];
+        reader.read_exact(&mut len_bytes)?;
+        let mut version_bytes = vec![0; u16::from_le_bytes(len_bytes) as usize];
+        reader.read_exact(&mut version_bytes)?;
+        let engine_version =
+            String::from_utf8(version_bytes).map_err(|_| TableFileError::NotATableFile)?;
+
+        Ok(Self {
+            format_version,
+            entry_size,
+            bucket_size,
+            bucket_count,
+            engine_version,
+        })
+    }
+
+    /// Why `self` can't be loaded into the current table, if it can't.
+    fn incompatibility(&self) -> Option<String> {
+        let current = Self::current();
+        if self.format_version != current.format_version {
+            Some(format!(
+                "file format {}, expected {}",
+                self.format_version, current.format_version
+            ))
+        } else if self.entry_size != current.entry_size || self.bucket_size != current.bucket_size {
+            Some(format!(
+                "{} entries of {} bytes per bucket, expected {} of {}",
+                self.bucket_size, self.entry_size, current.bucket_size, current.entry_size
+            ))
+        } else if self.bucket_count != current.bucket_count {
+            Some(format!(
+                "{} buckets, expected {}",
+                self.bucket_count, current.bucket_count
+            ))
+        } else if self.engine_version != current.engine_version {
+            Some(format!(
+                "saved by version {}, this is {}",
+                self.engine_version, current.engine_version
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Mate scores count the plies from the root, the table stores them counted from the node instead.
+///
+/// The same position can be reached at different plies, only the distance from the
+/// position itself to the mate holds for all of them. Other scores are stored as is.
+#[inline(always)]
+pub fn score_to_tt(score: i32, ply_from_root: i32) -> i32 {
+    if score >= MATE_THRESHOLD {
+        score.saturating_add(ply_from_root)
+    } else if score <= -MATE_THRESHOLD {
+        score.saturating_sub(ply_from_root)
+    } else {
+        score
+    }
+}
+
+/// Inverse of `score_to_tt`, the stored mate distance counted from the root again.
+#[inline(always)]
+pub fn score_from_tt(score: i32, ply_from_root: i32) -> i32 {
+    if score >= MATE_THRESHOLD {
+        score.saturating_sub(ply_from_root)
+    } else if score <= -MATE_THRESHOLD {
+        score.saturating_add(ply_from_root)
+    } else {
+        score
+    }
+}
+
+/// Hit rate and density of the table, printed by `bench`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TableStats {
+    pub entries_per_mb: u64,
+    pub lookups: u64,
+    /// Lookups that found their position, whether or not the entry was deep enough
+    pub hits: u64,
+}
+
+impl TableStats {
+    pub fn hit_rate(&self) -> f64 {
+        self.hits as f64 / self.lookups.max(1) as f64
     }
 }
 
+/// Entries sharing an index, one cache line so probing the whole bucket costs a single miss.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C, align(64))]
+struct Bucket {
+    entries: [TranspositionEntry; BUCKET_SIZE],
+}
+
 #[derive(Debug, Clone)]
 pub struct TranspositionTable {
-    table: Vec<TranspositionEntry>, // test static arrays and vectors
+    table: Vec<Bucket>, // test static arrays and vectors
+    /// Bumped at the start of every search, entries from older searches are replaced first
+    generation: u8,
 
     pub writes: Cell<u64>,
     pub lookups: Cell<u64>,
+    pub hits: Cell<u64>,
 }
 
 impl TranspositionTable {
     pub fn new() -> Self {
         Self {
-            table: vec![TranspositionEntry::default(); ENTRY_COUNT as usize],
+            table: vec![Bucket::default(); BUCKET_COUNT as usize],
+            generation: 0,
 
             writes: Cell::new(0),
             lookups: Cell::new(0),
+            hits: Cell::new(0),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.table.fill(Bucket::default());
+        self.generation = 0;
+    }
+
+    /// Ages every stored entry by one search.
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(GENERATION_STEP);
+    }
+
+    pub fn stats(&self) -> TableStats {
+        TableStats {
+            entries_per_mb: (1024 * 1024 / std::mem::size_of::<Bucket>() * BUCKET_SIZE) as u64,
+            lookups: self.lookups.get(),
+            hits: self.hits.get(),
         }
     }
 
+    #[inline(always)]
+    fn bucket(&self, hash: u64) -> &Bucket {
+        &self.table[(hash % BUCKET_COUNT) as usize]
+    }
+
+    #[inline(always)]
+    fn find(&self, hash: u64) -> Option<&TranspositionEntry> {
+        self.bucket(hash)
+            .entries
+            .iter()
+            .find(|entry| entry.matches(hash))
+    }
+
+    /// How much an entry is worth keeping, deep entries of the current search the most.
+    #[inline(always)]
+    fn replacement_worth(&self, entry: &TranspositionEntry) -> i32 {
+        let age = (self.generation.wrapping_sub(entry.generation()) / GENERATION_STEP) as i32;
+        entry.depth() - AGE_WEIGHT * age
+    }
+
+    /// Stores over the same position if present, otherwise over an empty entry or the least worth one.
+    ///
+    /// `score` is from the search's point of view, see `score_to_tt`.
     pub fn store_evaluation(
         &mut self,
         kind: NodeKind,
         hash: u64,
         depth: i32,
+        ply_from_root: i32,
         score: i32,
         best_move: Move,
     ) {
-        let key: usize = (hash % ENTRY_COUNT) as usize;
-        self.table[key] = TranspositionEntry::new(hash, kind, score, depth, best_move);
+        let score = score_to_tt(score, ply_from_root);
+        let index = (hash % BUCKET_COUNT) as usize;
+        let bucket = &self.table[index];
+
+        let slot = match bucket.entries.iter().position(|entry| entry.matches(hash)) {
+            Some(slot) => slot,
+            None => bucket
+                .entries
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, entry)| {
+                    if entry.is_empty() {
+                        i32::MIN
+                    } else {
+                        self.replacement_worth(entry)
+                    }
+                })
+                .map_or(0, |(slot, _)| slot),
+        };
+
+        let previous = self.table[index].entries[slot];
+        // a fail low has no best move of its own, keep the one found before
+        let best_move = if best_move == Move(0) && previous.matches(hash) {
+            previous.best_move()
+        } else {
+            best_move
+        };
+        self.table[index].entries[slot] =
+            TranspositionEntry::new(hash, kind, self.generation, score, depth, best_move);
         self.writes.set(self.writes.get() + 1);
     }
 
+    /// Replaces the move of the stored position, if there is one, and leaves its score as it is.
+    pub fn update_move(&mut self, hash: u64, best_move: Move) {
+        if best_move == Move(0) {
+            return;
+        }
+        let index = (hash % BUCKET_COUNT) as usize;
+        if let Some(entry) = self.table[index]
+            .entries
+            .iter_mut()
+            .find(|entry| entry.matches(hash))
+        {
+            entry.best_move = best_move.0;
+        }
+    }
+
+    /// Writes every entry behind a header describing the table.
+    ///
+    /// # Errors
+    /// If the file can't be written.
+    pub fn save(&self, path: &Path) -> Result<(), TableFileError> {
+        let mut writer = BufWriter::new(std::fs::File::create(path)?);
+        TableFileHeader::current().write(&mut writer)?;
+        writer.write_all(&[self.generation])?;
+        for bucket in &self.table {
+            for entry in &bucket.entries {
+                writer.write_all(&entry.to_bytes())?;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Replaces the table with one saved by `save`, the table is left untouched on errors.
+    ///
+    /// # Errors
+    /// If the file can't be read, isn't a saved table or was saved with a different table layout.
+    pub fn load(&mut self, path: &Path) -> Result<(), TableFileError> {
+        let mut reader = BufReader::new(std::fs::File::open(path)?);
+        let header = TableFileHeader::read(&mut reader)?;
+        if let Some(reason) = header.incompatibility() {
+            return Err(TableFileError::Incompatible(reason));
+        }
+
+        let mut generation = [0; 1];
+        reader.read_exact(&mut generation)?;
+        let mut table = vec![Bucket::default(); BUCKET_COUNT as usize];
+        let mut bytes = [0; 10];
+        for bucket in &mut table {
+            for entry in &mut bucket.entries {
+                reader.read_exact(&mut bytes)?;
+                *entry = TranspositionEntry::from_bytes(bytes);
+            }
+        }
+
+        self.table = table;
+        self.generation = generation[0];
+        Ok(())
+    }
+
+    /// Permille of the table used by the current search, estimated from the first 1000 entries
+    pub fn hashfull(&self) -> u32 {
+        let sample = self
+            .table
+            .iter()
+            .flat_map(|bucket| bucket.entries.iter())
+            .take(1000);
+        let mut sample_size = 0;
+        let mut used = 0;
+        for entry in sample {
+            sample_size += 1;
+            if !entry.is_empty() && entry.generation() == self.generation {
+                used += 1;
+            }
+        }
+        used * 1000 / sample_size.max(1)
+    }
+
     /// # Returns
     /// The move if in transposition table. If not returns NULL move
     pub fn get_entry_by_hash(&self, hash: u64) -> Move {
-        let index: usize = (hash % ENTRY_COUNT) as usize;
-        let entry: TranspositionEntry = self.table[index];
-        if entry.zobrist_hash == hash {
-            entry.best_move
-        } else {
-            Move(0)
-        }
+        self.find(hash).map_or(Move(0), |entry| entry.best_move())
+    }
+
+    /// Move, score, depth and kind of the stored entry, however deep it is.
+    pub fn probe_entry(&self, hash: u64, ply_from_root: i32) -> Option<(Move, i32, i32, NodeKind)> {
+        let entry = self.find(hash)?;
+        Some((
+            entry.best_move(),
+            score_from_tt(entry.score(), ply_from_root),
+            entry.depth(),
+            entry.kind(),
+        ))
     }
 
-    pub fn probe_hash(&self, hash: u64, depth: i32, alpha: i32, beta: i32) -> Option<(i32, Move)> {
+    /// The stored score if it's deep enough and settles the `alpha`-`beta` window at this node.
+    pub fn probe_hash(
+        &self,
+        hash: u64,
+        depth: i32,
+        ply_from_root: i32,
+        alpha: i32,
+        beta: i32,
+    ) -> Option<(i32, Move)> {
         self.lookups.set(self.lookups.get() + 1);
-        let index: usize = (hash % ENTRY_COUNT) as usize;
-        let entry: TranspositionEntry = self.table[index];
+        let entry = *self.find(hash)?;
+        self.hits.set(self.hits.get() + 1);
 
-        if entry.zobrist_hash == hash && entry.depth >= depth {
-            match entry.kind {
+        if entry.depth() >= depth {
+            let score = score_from_tt(entry.score(), ply_from_root);
+            match entry.kind() {
                 NodeKind::Exact => {
-                    if entry.score > alpha && entry.score < beta {
-                        return Some((entry.score, entry.best_move));
+                    if score > alpha && score < beta {
+                        return Some((score, entry.best_move()));
                     }
                 }
 
                 NodeKind::UpperBound => {
                     // We know the true score is ≤ entry.score
                     // If entry.score is already ≤ alpha, this position is hopeless
-                    if entry.score <= alpha {
-                        return Some((entry.score, entry.best_move));
+                    if score <= alpha {
+                        return Some((score, entry.best_move()));
                     }
                 }
 
                 NodeKind::LowerBound => {
                     // We know the true score is ≥ entry.score
                     // If entry.score is already ≥ beta, this move is too good
-                    if entry.score >= beta {
-                        return Some((entry.score, entry.best_move));
+                    if score >= beta {
+                        return Some((score, entry.best_move()));
                     }
                 }
             }
@@ -125,3 +546,116 @@ impl TranspositionTable {
         None
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::super::MATE;
+    use super::*;
+
+    /// Positions sharing bucket 1, told apart by the upper 32 bits
+    fn same_bucket(n: u64) -> u64 {
+        1 + (n << 12) * BUCKET_COUNT
+    }
+
+    #[test]
+    fn packed_entry_test1() {
+        assert_eq!(std::mem::size_of::<TranspositionEntry>(), 12);
+        assert_eq!(std::mem::size_of::<Bucket>(), 64);
+
+        let entry = TranspositionEntry::new(
+            same_bucket(1),
+            NodeKind::UpperBound,
+            8,
+            -29_990,
+            300,
+            Move(7),
+        );
+        assert!(entry.matches(same_bucket(1)));
+        assert!(!entry.matches(same_bucket(2)));
+        assert_eq!(entry.kind(), NodeKind::UpperBound);
+        assert_eq!(entry.generation(), 8);
+        assert_eq!(entry.score(), -29_990);
+        assert_eq!(entry.depth(), u8::MAX as i32);
+        assert_eq!(entry.best_move(), Move(7));
+    }
+
+    #[test]
+    fn mate_score_test1() {
+        let mut tt = TranspositionTable::new();
+        let hash = same_bucket(1);
+
+        // mate in 3 plies from a node 5 plies deep, reached again at ply 1
+        tt.store_evaluation(NodeKind::Exact, hash, 4, 5, MATE - 8, Move(1));
+        let (score, _) = tt.probe_hash(hash, 4, 1, -MATE, MATE).expect("stored");
+        assert_eq!(score, MATE - 4);
+
+        // ordinary scores don't depend on the ply
+        tt.store_evaluation(NodeKind::Exact, hash, 4, 5, 120, Move(1));
+        let (score, _) = tt.probe_hash(hash, 4, 1, -MATE, MATE).expect("stored");
+        assert_eq!(score, 120);
+
+        tt.store_evaluation(NodeKind::UpperBound, hash, 4, 6, -MATE + 10, Move(0));
+        assert_eq!(
+            tt.probe_hash(hash, 4, 2, 0, 50).map(|(score, _)| score),
+            Some(-MATE + 6)
+        );
+    }
+
+    #[test]
+    fn save_load_test1() {
+        let path = std::env::temp_dir().join(format!("giffibot_tt_{}.bin", std::process::id()));
+        let mut tt = TranspositionTable::new();
+        tt.new_search();
+        tt.store_evaluation(NodeKind::UpperBound, same_bucket(1), 7, 0, -250, Move(9));
+        tt.save(&path).expect("saved");
+
+        let mut loaded = TranspositionTable::new();
+        loaded.load(&path).expect("loaded");
+        assert_eq!(loaded.generation, tt.generation);
+        assert_eq!(
+            loaded.probe_hash(same_bucket(1), 7, 0, 0, 100),
+            Some((-250, Move(9)))
+        );
+
+        // a file from another version is rejected and leaves the table as it was
+        let mut file = std::fs::read(&path).expect("saved");
+        let version_start = FILE_MAGIC.len() + 4 * 3 + 8 + 2;
+        file[version_start] = b'X';
+        std::fs::write(&path, file).expect("written");
+        assert!(matches!(
+            loaded.load(&path),
+            Err(TableFileError::Incompatible(_))
+        ));
+        assert_eq!(loaded.get_entry_by_hash(same_bucket(1)), Move(9));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn bucket_replacement_test1() {
+        let mut tt = TranspositionTable::new();
+        let deep = same_bucket(1);
+        for n in 2..=BUCKET_SIZE as u64 {
+            tt.store_evaluation(NodeKind::Exact, same_bucket(n), 1, 0, 20, Move(n as u16));
+        }
+        tt.store_evaluation(NodeKind::Exact, deep, 10, 0, 50, Move(1));
+
+        // the bucket is full, a shallow entry makes room and the deep one stays
+        let newcomer = same_bucket(100);
+        tt.store_evaluation(NodeKind::Exact, newcomer, 3, 0, 30, Move(100));
+        assert_eq!(tt.get_entry_by_hash(deep), Move(1));
+        assert_eq!(tt.get_entry_by_hash(newcomer), Move(100));
+        assert_eq!(tt.get_entry_by_hash(same_bucket(2)), Move(0));
+
+        // from an old enough search even the deep entry loses to shallow new ones
+        for _ in 0..2 {
+            tt.new_search();
+        }
+        assert_eq!(tt.hashfull(), 0);
+        for n in 200..200 + BUCKET_SIZE as u64 {
+            tt.store_evaluation(NodeKind::Exact, same_bucket(n), 1, 0, 20, Move(n as u16));
+        }
+        assert_eq!(tt.get_entry_by_hash(deep), Move(0));
+        assert_eq!(tt.hashfull(), BUCKET_SIZE as u32);
+    }
+}
@@ -1,3 +1,4 @@
+use super::eval_trace::{EvalTerm, EvalTrace, EvalTracer, NoTrace};
 use super::masks::PASSED_PAWN_MASK;
 use super::GiffiBot;
 use bitschess::prelude::*;
@@ -112,17 +113,31 @@ pub const fn get_piece_value(piece_type: PieceType) -> i32 {
 
 impl GiffiBot {
     pub fn evaluate(&self) -> i32 {
+        self.evaluate_traced(&mut NoTrace)
+    }
+
+    /// `evaluate` with its per term breakdown, for the `eval` command.
+    pub fn trace_eval(&self) -> EvalTrace {
+        let mut trace = EvalTrace::new();
+        trace.eval = self.evaluate_traced(&mut trace);
+        trace.white_to_move = self.board.get_turn() == PieceColor::White;
+        trace
+    }
+
+    pub fn evaluate_traced<T: EvalTracer>(&self, tracer: &mut T) -> i32 {
         let mut eval = 0i32;
 
         let mut all_pieces = self.board.side_bitboards[0] | self.board.side_bitboards[1];
         let end_game = self.is_end_game();
+        tracer.phase(end_game, self.phase_material());
 
         while all_pieces != 0 {
             let square = BoardHelper::bitscan_forward(all_pieces);
             all_pieces ^= 1u64 << square;
 
             let piece = self.board.get_piece(square);
-            let position = if piece.get_color() == PieceColor::Black {
+            let color = piece.get_color();
+            let position = if color == PieceColor::Black {
                 square
             } else {
                 63 - square
@@ -131,7 +146,6 @@ impl GiffiBot {
 
             match piece.get_piece_type() {
                 PieceType::Pawn => {
-                    let color = piece.get_color();
                     let penalty = if self.contains_multiple_pawns_this_file(color, square) {
                         DOUBLED_PAWN_PENALTY
                     } else {
@@ -148,30 +162,45 @@ impl GiffiBot {
                         0
                     };
 
+                    tracer.term(EvalTerm::PawnTable, color, PAWN_POSITION[position as usize]);
+                    tracer.term(EvalTerm::DoubledPawns, color, -penalty);
+                    tracer.term(EvalTerm::PassedPawns, color, passed);
+                    tracer.term(EvalTerm::PawnsTowardsKing, color, atk_king);
                     positional_scoring =
                         PAWN_POSITION[position as usize] + passed + atk_king - penalty;
                 }
                 PieceType::Knight => {
                     positional_scoring = KNIGHT_POSITION[position as usize];
+                    tracer.term(EvalTerm::KnightTable, color, positional_scoring);
                 }
                 PieceType::Bishop => {
                     positional_scoring = BISHOP_POSITION[position as usize];
+                    tracer.term(EvalTerm::BishopTable, color, positional_scoring);
                 }
                 PieceType::Rook => {
                     positional_scoring = ROOK_POSITION[position as usize]; //+ bonus;
+                    tracer.term(EvalTerm::RookTable, color, positional_scoring);
                 }
                 PieceType::Queen => {
                     positional_scoring = QUEEN_POSITION[position as usize]; //+ bonus;
+                    tracer.term(EvalTerm::QueenTable, color, positional_scoring);
                 }
                 PieceType::King => {
                     if !end_game {
                         positional_scoring = KING_POSITION[position as usize];
+                        tracer.term(EvalTerm::KingTable, color, positional_scoring);
                     } else {
                         // In endgames, prefer having king in the middle and forcing the enemy king into the corner or edge.
-                        positional_scoring = KING_POSITION_END[position as usize]
-                            + CENTER_MANHATTAN_DISTANCE
-                                [self.board.get_king_square(piece.get_color().flipped()) as usize]
-                                * 10;
+                        let enemy_king_edge = CENTER_MANHATTAN_DISTANCE
+                            [self.board.get_king_square(color.flipped()) as usize]
+                            * 10;
+                        tracer.term(
+                            EvalTerm::KingTable,
+                            color,
+                            KING_POSITION_END[position as usize],
+                        );
+                        tracer.term(EvalTerm::KingEndgame, color, enemy_king_edge);
+                        positional_scoring = KING_POSITION_END[position as usize] + enemy_king_edge;
                     }
                 }
                 _ => {
@@ -179,6 +208,11 @@ impl GiffiBot {
                 }
             }
 
+            tracer.term(
+                EvalTerm::Material,
+                color,
+                get_piece_value(piece.get_piece_type()),
+            );
             if piece.is_black() {
                 eval -= get_piece_value(piece.get_piece_type()) + positional_scoring;
             } else {
@@ -378,4 +412,28 @@ mod test {
             false
         );
     }
+
+    #[test]
+    fn trace_eval_test1() {
+        let mut board = ChessBoard::new();
+        board
+            .parse_fen("6k1/5ppp/8/3P4/8/8/PP3PPP/3R2K1 b - - 0 1")
+            .expect("valid fen");
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let bot = GiffiBot::new(board, stop);
+        let trace = bot.trace_eval();
+
+        // the terms add up to the evaluation, black to move sees it negated
+        let total = EvalTerm::ALL
+            .iter()
+            .map(|term| trace.total(*term))
+            .sum::<i32>();
+        assert_eq!(-total, bot.evaluate());
+        assert_eq!(trace.eval, bot.evaluate());
+        assert!(trace.end_game);
+        assert_eq!(trace.total(EvalTerm::Material), 800);
+        // a2, b2 and d5
+        assert_eq!(trace.white(EvalTerm::PassedPawns), 3 * PASSED_PAWN_REWARD);
+    }
 }
@@ -0,0 +1,99 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::thread::JoinHandle;
+
+use crate::chessbot::{go::SearchLimits, reporter::SearchReporter, GiffiBot};
+use bitschess::prelude::*;
+
+/// Owns the bot and the one thread searching with it.
+///
+/// A new search always waits for the previous one to finish, so every started search
+/// reports exactly one `bestmove` and searches never overlap.
+#[derive(Debug)]
+pub struct SearchController {
+    stop_search: Arc<AtomicBool>,
+    /// The bot while idle, the search thread hands it back when joined.
+    bot: Option<GiffiBot>,
+    search: Option<JoinHandle<GiffiBot>>,
+}
+
+impl SearchController {
+    pub fn new() -> Self {
+        Self {
+            stop_search: Arc::new(AtomicBool::new(false)),
+            bot: None,
+            search: None,
+        }
+    }
+
+    /// Stops the current search (if any) and starts searching `board` on the search thread.
+    pub fn start<R: SearchReporter + 'static>(
+        &mut self,
+        board: ChessBoard,
+        limits: SearchLimits,
+        reporter: R,
+    ) {
+        let mut bot = self.take_bot();
+        bot.board = board;
+        bot.set_reporter(reporter);
+
+        self.stop_search.store(false, Ordering::Relaxed);
+        self.search = Some(std::thread::spawn(move || {
+            bot.go(&limits);
+            bot
+        }));
+    }
+
+    /// Signals the search to stop and waits for it to report its move.
+    pub fn stop(&mut self) {
+        if self.search.is_some() {
+            self.stop_search.store(true, Ordering::Relaxed);
+        }
+        self.wait();
+    }
+
+    /// Waits for the current search to finish on its own.
+    pub fn wait(&mut self) {
+        if let Some(handle) = self.search.take() {
+            match handle.join() {
+                Ok(bot) => self.bot = Some(bot),
+                // the bot went down with the thread, a new one is made on the next search
+                Err(_) => self.bot = None,
+            }
+        }
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.search
+            .as_ref()
+            .map_or(false, |handle| !handle.is_finished())
+    }
+
+    /// The idle bot, stops the current search first.
+    pub fn bot_mut(&mut self) -> &mut GiffiBot {
+        self.stop();
+        let bot = self.take_bot();
+        self.bot.insert(bot)
+    }
+
+    fn take_bot(&mut self) -> GiffiBot {
+        self.stop();
+        self.bot
+            .take()
+            .unwrap_or_else(|| GiffiBot::new(ChessBoard::new(), Arc::clone(&self.stop_search)))
+    }
+}
+
+impl Default for SearchController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for SearchController {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -1,6 +1,30 @@
+mod analysis;
+mod bench;
 mod chessbot;
+mod controller;
+mod perft;
+mod pgn;
 mod uci;
+mod xboard;
 
+pub use analysis::{
+    analyze_game, AnalysisError, AnalysisLimit, AnnotatedGame, AnnotatedMove, Judgement,
+    PlayerSummary,
+};
+pub use bench::{bench, bench_with, BenchResult, DEFAULT_BENCH_DEPTH};
 pub use bitschess::prelude::*;
-pub use chessbot::GiffiBot;
-pub use uci::UCIEngine;
+pub use chessbot::{
+    eval_trace::{EvalTerm, EvalTrace, EvalTracer},
+    go::SearchLimits,
+    internal_iteration::InternalIteration,
+    reporter::{CallbackReporter, CollectingReporter, SearchReporter, SilentReporter, UciReporter},
+    root::RootMove,
+    search_info::{Score, SearchInfo, SearchResult},
+    skill::Skill,
+    GiffiBot, TableFileError, TableStats,
+};
+pub use controller::SearchController;
+pub use perft::{divide, parse_epd, perft, run_suite, PerftCase, PerftFailure, BUNDLED_SUITE};
+pub use pgn::{move_to_san, parse_pgn, san_to_move, PgnGame};
+pub use uci::{UCIEngine, UciParseError};
+pub use xboard::{XBoardEngine, XBoardError};
@@ -1,23 +1,93 @@
+mod analysis;
+mod bench;
 mod chessbot;
+mod controller;
+mod perft;
+mod pgn;
 mod uci;
+mod xboard;
 
 use bitschess::prelude::*;
+use std::sync::mpsc::Receiver;
+
 use uci::UCIEngine;
+use xboard::XBoardEngine;
 
 fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    if let Some(subcommand) = args.first() {
+        match subcommand.as_str() {
+            "analyze" => {
+                if let Err(e) = analysis::run_cli(&args[1..]) {
+                    eprintln!("{:?}", e);
+                    std::process::exit(1);
+                }
+                return;
+            }
+            "bench" => {
+                if let Err(e) = bench::run_cli(&args[1..]) {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+                return;
+            }
+            _ => {
+                eprintln!("unknown subcommand '{}'", subcommand);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // stdin is read on its own thread, so the engine is never stuck waiting for input
+    let (sender, receiver) = std::sync::mpsc::channel::<String>();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lines() {
+            match line {
+                Ok(line) => {
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    });
+
+    println!("GiffiBot!");
+    // the first command picks the protocol, CECP GUIs always start with 'xboard'
+    match receiver.iter().find(|line| !line.trim().is_empty()) {
+        Some(first) if first.trim() == "xboard" => run_xboard(first, receiver),
+        Some(first) => run_uci(first, receiver),
+        None => {}
+    }
+}
+
+// the sender hangs up on EOF, which is treated like 'quit'
+fn run_uci(first: String, receiver: Receiver<String>) {
     let mut uci = UCIEngine::new();
     uci.board.parse_fen(STARTPOS_FEN).expect("valid fen");
 
-    println!("GiffiBot!");
-    loop {
-        let line = std::io::stdin().lines().next().unwrap().unwrap();
-
-        if &line == "quit" {
-            return;
-        } else {
-            if let Err(e) = uci.execute_cmd(&line) {
-                println!("{:?}", e);
-            }
+    for line in std::iter::once(first).chain(receiver) {
+        if let Err(e) = uci.execute_cmd(line.trim()) {
+            println!("info string {}", e);
+        }
+        if line.trim() == "quit" {
+            break;
+        }
+    }
+    uci.quit();
+}
+
+fn run_xboard(first: String, receiver: Receiver<String>) {
+    let mut xboard = XBoardEngine::new();
+
+    for line in std::iter::once(first).chain(receiver) {
+        if let Err(e) = xboard.execute_cmd(line.trim()) {
+            println!("{}", e);
+        }
+        if line.trim() == "quit" {
+            break;
         }
     }
+    xboard.quit();
 }
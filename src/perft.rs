@@ -0,0 +1,165 @@
+//! Move generation checks: perft divide and a suite of positions with known node counts.
+//!
+//! Counts are made with `get_legal_moves` and make/unmake, the same calls the search uses,
+//! so a BitsChess revision that breaks them shows up here first.
+
+use std::time::{Duration, Instant};
+
+use bitschess::prelude::*;
+
+/// `testing/perft.epd`, bundled so the suite runs without the repository around.
+pub const BUNDLED_SUITE: &str = include_str!("../testing/perft.epd");
+
+/// Leaf nodes `depth` plies from the current position.
+pub fn perft(board: &mut ChessBoard, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = board.get_legal_moves();
+    // bulk counting, the leaves don't need to be made
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    let mut nodes = 0;
+    for m in moves.iter() {
+        board.make_move(*m, true);
+        nodes += perft(board, depth - 1);
+        let _ = board.unmake_move();
+    }
+    nodes
+}
+
+/// Leaf nodes under every root move.
+pub fn divide(board: &mut ChessBoard, depth: u32) -> Vec<(Move, u64)> {
+    let moves = board.get_legal_moves();
+    let mut counts = vec![];
+    for m in moves.iter() {
+        board.make_move(*m, true);
+        counts.push((*m, perft(board, depth.saturating_sub(1))));
+        let _ = board.unmake_move();
+    }
+    counts
+}
+
+/// Prints the divide counts sorted by move, then the total, time and nps.
+pub fn print_divide(board: &mut ChessBoard, depth: u32) {
+    let start = Instant::now();
+    let mut counts = divide(board, depth);
+    let time = start.elapsed();
+
+    counts.sort_by_key(|(m, _)| m.to_uci());
+    for (m, nodes) in &counts {
+        println!("{}: {}", m.to_uci(), nodes);
+    }
+    let total = counts.iter().map(|(_, nodes)| nodes).sum::<u64>();
+    println!();
+    println!("Nodes searched: {}", total);
+    println!("Time (ms): {}", time.as_millis());
+    println!("Nodes/second: {}", nodes_per_second(total, time));
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PerftCase {
+    pub fen: String,
+    /// (depth, nodes)
+    pub expected: Vec<(u32, u64)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PerftFailure {
+    pub fen: String,
+    pub depth: u32,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+/// Parses lines like `<fen> ;D1 20 ;D2 400`, empty lines and `#` comments are skipped.
+///
+/// # Errors
+/// The line number and the part that couldn't be read.
+pub fn parse_epd(text: &str) -> Result<Vec<PerftCase>, String> {
+    let mut cases = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line.split(';');
+        let fen = fields.next().unwrap_or("").trim().to_string();
+        let mut expected = vec![];
+        for field in fields {
+            let parsed = field
+                .trim()
+                .strip_prefix('D')
+                .and_then(|field| field.split_once(' '))
+                .and_then(|(depth, nodes)| {
+                    Some((
+                        depth.parse::<u32>().ok()?,
+                        nodes.trim().parse::<u64>().ok()?,
+                    ))
+                });
+            match parsed {
+                Some(count) => expected.push(count),
+                None => {
+                    return Err(format!(
+                        "line {}: invalid perft count '{}'",
+                        i + 1,
+                        field.trim()
+                    ))
+                }
+            }
+        }
+        cases.push(PerftCase { fen, expected });
+    }
+    Ok(cases)
+}
+
+/// Checks every count up to `max_depth`, printing one line per checked count.
+pub fn run_suite(cases: &[PerftCase], max_depth: u32) -> Vec<PerftFailure> {
+    let mut failures = vec![];
+    let mut board = ChessBoard::new();
+    let mut total_nodes = 0;
+    let start = Instant::now();
+
+    for case in cases {
+        if board.parse_fen(&case.fen).is_err() {
+            println!("invalid FEN {}", case.fen);
+            continue;
+        }
+        for (depth, expected) in case
+            .expected
+            .iter()
+            .filter(|(depth, _)| *depth <= max_depth)
+        {
+            let actual = perft(&mut board, *depth);
+            total_nodes += actual;
+
+            let status = if actual == *expected { "ok" } else { "FAILED" };
+            println!("{:<6} D{} {:>10} {}", status, depth, actual, case.fen);
+            if actual != *expected {
+                failures.push(PerftFailure {
+                    fen: case.fen.clone(),
+                    depth: *depth,
+                    expected: *expected,
+                    actual,
+                });
+            }
+        }
+    }
+
+    let time = start.elapsed();
+    println!(
+        "{} failed, {} nodes in {} ms ({} nps)",
+        failures.len(),
+        total_nodes,
+        time.as_millis(),
+        nodes_per_second(total_nodes, time)
+    );
+    failures
+}
+
+fn nodes_per_second(nodes: u64, time: Duration) -> u64 {
+    (nodes as f64 / time.as_secs_f64().max(0.001)) as u64
+}
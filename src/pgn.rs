@@ -0,0 +1,306 @@
+use bitschess::prelude::*;
+
+#[derive(Debug, Clone, Default)]
+pub struct PgnGame {
+    /// Tag pairs in the order they appeared in the file.
+    pub headers: Vec<(String, String)>,
+    /// Mainline moves in SAN, comments, variations and NAGs are dropped.
+    pub moves: Vec<String>,
+    pub result: String,
+}
+
+impl PgnGame {
+    pub fn get_header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        if let Some(header) = self.headers.iter_mut().find(|(key, _)| key == name) {
+            header.1 = value.to_string();
+        } else {
+            self.headers.push((name.to_string(), value.to_string()));
+        }
+    }
+}
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Parses every game in a PGN file. Only the mainline is kept.
+pub fn parse_pgn(pgn: &str) -> Vec<PgnGame> {
+    let mut games = Vec::new();
+    let mut game = PgnGame::default();
+    let mut movetext = String::new();
+
+    for line in pgn.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('%') {
+            continue; // escape mechanism
+        }
+
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            // a tag after the movetext begins a new game
+            if !movetext.trim().is_empty() {
+                games.push(finish_game(std::mem::take(&mut game), &movetext));
+                movetext.clear();
+            }
+            if let Some(tag) = parse_tag(trimmed) {
+                game.headers.push(tag);
+            }
+            continue;
+        }
+
+        movetext.push_str(line);
+        movetext.push('\n');
+    }
+
+    if !movetext.trim().is_empty() || !game.headers.is_empty() {
+        games.push(finish_game(game, &movetext));
+    }
+    games
+}
+
+fn finish_game(mut game: PgnGame, movetext: &str) -> PgnGame {
+    let (moves, result) = parse_movetext(movetext);
+    game.moves = moves;
+    game.result = result.unwrap_or_else(|| game.get_header("Result").unwrap_or("*").to_string());
+    game
+}
+
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = &line[1..line.len() - 1];
+    let (key, rest) = inner.split_once(char::is_whitespace)?;
+    let value = rest.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((
+        key.to_string(),
+        value.replace("\\\"", "\"").replace("\\\\", "\\"),
+    ))
+}
+
+fn parse_movetext(movetext: &str) -> (Vec<String>, Option<String>) {
+    let mut moves = Vec::new();
+    let mut result = None;
+    let mut variation_depth = 0;
+    let mut chars = movetext.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+            }
+            ';' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' => variation_depth += 1,
+            ')' => variation_depth -= 1,
+            c if c.is_whitespace() => {}
+            _ => {
+                let mut token = String::from(c);
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || "{}();".contains(next) {
+                        break;
+                    }
+                    token.push(next);
+                    chars.next();
+                }
+
+                if variation_depth > 0 || token.starts_with('$') {
+                    continue;
+                }
+                if RESULTS.contains(&token.as_str()) {
+                    result = Some(token);
+                    continue;
+                }
+
+                let san = strip_move_number(&token);
+                if !san.is_empty() {
+                    moves.push(san.to_string());
+                }
+            }
+        }
+    }
+
+    (moves, result)
+}
+
+/// Strips move numbers, "12." "12..." and "12.e4". Zero-style castling "0-0" is kept as is.
+fn strip_move_number(token: &str) -> &str {
+    let after_digits = token.trim_start_matches(|c: char| c.is_ascii_digit());
+    if after_digits.len() < token.len() && after_digits.starts_with('.') {
+        after_digits.trim_start_matches('.')
+    } else {
+        token
+    }
+}
+
+fn piece_letter(piece_type: PieceType) -> Option<char> {
+    match piece_type {
+        PieceType::Knight => Some('N'),
+        PieceType::Bishop => Some('B'),
+        PieceType::Rook => Some('R'),
+        PieceType::Queen => Some('Q'),
+        PieceType::King => Some('K'),
+        _ => None,
+    }
+}
+
+/// Standard algebraic notation for a legal move in the current position, including the check suffix.
+pub fn move_to_san(board: &mut ChessBoard, chess_move: Move) -> String {
+    let uci = chess_move.to_uci();
+    let (from, to) = (&uci[0..2], &uci[2..4]);
+    let piece_type = board.get_piece(chess_move.get_from_idx()).get_piece_type();
+
+    let mut san = String::new();
+    if chess_move.get_flag() == MoveFlag::Castle {
+        san.push_str(if to.starts_with('g') { "O-O" } else { "O-O-O" });
+    } else {
+        let is_capture = !board.get_piece(chess_move.get_to_idx()).is_none()
+            || (piece_type == PieceType::Pawn && from[0..1] != to[0..1]);
+
+        if let Some(letter) = piece_letter(piece_type) {
+            san.push(letter);
+
+            // Disambiguate between identical pieces able to reach the same square
+            let others = board
+                .get_legal_moves()
+                .iter()
+                .filter(|m| {
+                    **m != chess_move
+                        && m.get_to_idx() == chess_move.get_to_idx()
+                        && board.get_piece(m.get_from_idx()).get_piece_type() == piece_type
+                })
+                .map(|m| m.to_uci())
+                .collect::<Vec<String>>();
+
+            if !others.is_empty() {
+                let same_file = others.iter().any(|o| o[0..1] == from[0..1]);
+                let same_rank = others.iter().any(|o| o[1..2] == from[1..2]);
+                if !same_file {
+                    san.push_str(&from[0..1]);
+                } else if !same_rank {
+                    san.push_str(&from[1..2]);
+                } else {
+                    san.push_str(from);
+                }
+            }
+        } else if is_capture {
+            san.push_str(&from[0..1]);
+        }
+
+        if is_capture {
+            san.push('x');
+        }
+        san.push_str(to);
+
+        if uci.len() > 4 {
+            san.push('=');
+            san.push_str(&uci[4..].to_uppercase());
+        }
+    }
+
+    board.make_move(chess_move, true);
+    if board.is_king_in_check(board.get_turn()) {
+        if board.get_legal_moves().is_empty() {
+            san.push('#');
+        } else {
+            san.push('+');
+        }
+    }
+    let _ = board.unmake_move();
+
+    san
+}
+
+fn normalize_san(san: &str) -> String {
+    san.replace('0', "O")
+        .chars()
+        .filter(|c| !"+#!?=".contains(*c))
+        .collect()
+}
+
+/// Finds the legal move matching `san`, check/annotation suffixes are ignored.
+pub fn san_to_move(board: &mut ChessBoard, san: &str) -> Option<Move> {
+    let wanted = normalize_san(san);
+    let moves = board.get_legal_moves();
+    moves
+        .iter()
+        .copied()
+        .find(|m| normalize_san(&move_to_san(board, *m)) == wanted)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_pgn_test1() {
+        let pgn = r#"[Event "Test"]
+[White "A"]
+[Black "B"]
+[Result "1-0"]
+
+1. e4 {best by test} e5 2. Nf3 (2. f4 exf4) 2... Nc6 $1 3. Bb5 a6?! 1-0
+
+[Event "Second"]
+
+1. d4 d5 *
+"#;
+        let games = parse_pgn(pgn);
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].get_header("White"), Some("A"));
+        assert_eq!(
+            games[0].moves,
+            vec!["e4", "e5", "Nf3", "Nc6", "Bb5", "a6?!"]
+        );
+        assert_eq!(games[0].result, "1-0");
+        assert_eq!(games[1].moves, vec!["d4", "d5"]);
+        assert_eq!(games[1].result, "*");
+    }
+
+    #[test]
+    fn parse_pgn_test2() {
+        let pgn = "1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. 0-0 Nf6 5.d3 0-0 *";
+        let games = parse_pgn(pgn);
+        assert_eq!(
+            games[0].moves,
+            vec!["e4", "e5", "Nf3", "Nc6", "Bc4", "Bc5", "0-0", "Nf6", "d3", "0-0"]
+        );
+
+        let mut board = ChessBoard::new();
+        board
+            .parse_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1")
+            .expect("valid fen");
+        let castle = san_to_move(&mut board, "0-0-0").expect("legal");
+        assert_eq!(castle.to_uci(), "e1c1");
+    }
+
+    #[test]
+    fn move_to_san_test1() {
+        let mut board = ChessBoard::new();
+        board
+            .parse_fen("r3k2r/8/8/8/8/2N3N1/8/R3K2R w KQkq - 0 1")
+            .expect("valid fen");
+
+        let castle = san_to_move(&mut board, "O-O").expect("legal");
+        assert_eq!(castle.to_uci(), "e1g1");
+
+        let knight = san_to_move(&mut board, "Nce4").expect("legal");
+        assert_eq!(knight.to_uci(), "c3e4");
+        assert_eq!(move_to_san(&mut board, knight), "Nce4");
+
+        let rook = san_to_move(&mut board, "Rxa8+").expect("legal");
+        assert_eq!(move_to_san(&mut board, rook), "Rxa8+");
+
+        assert!(san_to_move(&mut board, "Ne4").is_none());
+    }
+}
@@ -1,28 +1,117 @@
-use std::sync::{atomic::AtomicBool, Arc};
 use std::time::Duration;
 
-use crate::chessbot::GiffiBot;
+use crate::bench;
+use crate::chessbot::skill::{self, Skill};
+use crate::chessbot::{
+    go::SearchLimits, internal_iteration::InternalIteration, reporter::UciReporter, root,
+};
+use crate::controller::SearchController;
+use crate::perft;
 use bitschess::prelude::*;
 
-#[derive(Debug)]
+/// Every argument `go` understands, used to find where `searchmoves` ends.
+const GO_ARGUMENTS: [&str; 13] = [
+    "searchmoves",
+    "ponder",
+    "wtime",
+    "btime",
+    "winc",
+    "binc",
+    "movestogo",
+    "depth",
+    "nodes",
+    "mate",
+    "movetime",
+    "infinite",
+    "perft",
+];
+
+/// Centipawns, either way
+const MAX_CONTEMPT: i32 = 100;
+/// Milliseconds
+const MAX_CURRMOVE_DELAY: u64 = 60_000;
+
+type ArgIter<'a> = std::iter::Peekable<std::vec::IntoIter<&'a str>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum UciParseError {
-    InvalidSyntax,
+    UnknownCommand(String),
+    /// The reason given by the FEN parser
+    InvalidFen(String),
+    /// `index` is the 1 based position in the `moves` list
+    IllegalMove {
+        index: usize,
+        chess_move: String,
+    },
+    InvalidOptionValue {
+        name: String,
+        value: String,
+    },
+    InvalidArgument {
+        name: String,
+        value: String,
+    },
+    MissingArgument(String),
+    /// `savehash` or `loadhash` failed
+    HashFile {
+        path: String,
+        reason: String,
+    },
 }
 
+impl std::fmt::Display for UciParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownCommand(cmd) => write!(f, "unknown command '{}'", cmd),
+            Self::InvalidFen(reason) => write!(f, "invalid FEN: {}", reason),
+            Self::IllegalMove { index, chess_move } => {
+                write!(f, "illegal move '{}' at index {}", chess_move, index)
+            }
+            Self::InvalidOptionValue { name, value } => {
+                write!(f, "invalid value '{}' for option '{}'", value, name)
+            }
+            Self::InvalidArgument { name, value } => {
+                write!(f, "invalid value '{}' for '{}'", value, name)
+            }
+            Self::MissingArgument(name) => write!(f, "missing argument for '{}'", name),
+            Self::HashFile { path, reason } => write!(f, "hash file '{}': {}", path, reason),
+        }
+    }
+}
+
+impl std::error::Error for UciParseError {}
+
 pub struct UCIEngine {
     pub board: ChessBoard,
-    stop_search: Arc<AtomicBool>,
+    controller: SearchController,
 
     option_movetime: Option<Duration>,
+    /// Reject a `position` command with an illegal move instead of keeping the moves before it
+    option_strict_position: bool,
+    /// `UCI_Elo` is only used while `UCI_LimitStrength` is on, `Skill Level` otherwise
+    option_limit_strength: bool,
+    option_elo: i32,
+    option_skill_level: i32,
+    option_contempt: i32,
+    /// `info currmove` lines start after this much search time
+    option_currmove_delay: Duration,
+    option_internal_iteration: InternalIteration,
 }
 
 impl UCIEngine {
     pub fn new() -> Self {
         Self {
-            stop_search: Arc::new(AtomicBool::new(false)),
+            controller: SearchController::new(),
             board: ChessBoard::new(),
 
             option_movetime: None,
+            option_strict_position: false,
+            option_limit_strength: false,
+            option_elo: 1500,
+            option_skill_level: skill::MAX_SKILL_LEVEL,
+            option_contempt: 0,
+            option_currmove_delay: root::DEFAULT_CURRMOVE_DELAY,
+            option_internal_iteration: InternalIteration::default(),
         }
     }
 
@@ -45,12 +134,85 @@ impl UCIEngine {
                 "board" | "d" => {
                     println!("{}", self.board);
                 }
+                "eval" => {
+                    let bot = self.controller.bot_mut();
+                    bot.board = self.board.clone();
+                    println!("{}", bot.trace_eval());
+                }
+                // savehash <file> / loadhash <file>, the path may contain spaces
+                "savehash" | "loadhash" => {
+                    let path = args.collect::<Vec<&str>>().join(" ");
+                    if path.is_empty() {
+                        return Err(UciParseError::MissingArgument(cmd.to_string()));
+                    }
+                    let bot = self.controller.bot_mut();
+                    let result = if cmd == "savehash" {
+                        bot.save_tt(std::path::Path::new(&path))
+                    } else {
+                        bot.load_tt(std::path::Path::new(&path))
+                    };
+                    return result.map_err(|error| UciParseError::HashFile {
+                        path,
+                        reason: error.to_string(),
+                    });
+                }
+                "perft" => {
+                    let depth = Self::parse_next::<u32>(&mut args, cmd)?;
+                    perft::print_divide(&mut self.board, depth);
+                }
+                // perftsuite [max depth], every count in the bundled suite by default
+                "perftsuite" => {
+                    let max_depth = match args.peek() {
+                        Some(arg) if !arg.is_empty() => Self::parse_next::<u32>(&mut args, cmd)?,
+                        _ => u32::MAX,
+                    };
+                    let cases = perft::parse_epd(perft::BUNDLED_SUITE).expect("valid suite");
+                    perft::run_suite(&cases, max_depth);
+                }
+                // bench [depth] [off|reduction|deepening]
+                "bench" => {
+                    let depth = match args.peek() {
+                        Some(arg) if !arg.is_empty() => Self::parse_next::<i32>(&mut args, cmd)?,
+                        _ => bench::DEFAULT_BENCH_DEPTH,
+                    };
+                    let internal_iteration = match args.peek() {
+                        Some(arg) if !arg.is_empty() => Self::parse_next(&mut args, cmd)?,
+                        _ => self.option_internal_iteration,
+                    };
+                    self.controller.stop();
+                    bench::print_summary(&bench::bench_with(depth, internal_iteration));
+                }
 
                 // UCI commands
                 "uci" => {
                     println!("id name GiffiBot");
                     println!("id author Miklas ('Giffi') Karjalainen");
                     println!("option name movetime type spin default 0 min 0 max 10000");
+                    println!("option name StrictPosition type check default false");
+                    println!("option name UCI_LimitStrength type check default false");
+                    println!(
+                        "option name UCI_Elo type spin default 1500 min {} max {}",
+                        skill::MIN_ELO,
+                        skill::MAX_ELO
+                    );
+                    println!(
+                        "option name Skill Level type spin default {} min 0 max {}",
+                        skill::MAX_SKILL_LEVEL,
+                        skill::MAX_SKILL_LEVEL
+                    );
+                    println!(
+                        "option name Contempt type spin default 0 min -{} max {}",
+                        MAX_CONTEMPT, MAX_CONTEMPT
+                    );
+                    println!(
+                        "option name CurrmoveDelay type spin default {} min 0 max {}",
+                        root::DEFAULT_CURRMOVE_DELAY.as_millis(),
+                        MAX_CURRMOVE_DELAY
+                    );
+                    println!(
+                        "option name InternalIteration type combo default {} var off var reduction var deepening",
+                        InternalIteration::default()
+                    );
                     println!("uciok");
                 }
                 "isready" => {
@@ -58,208 +220,319 @@ impl UCIEngine {
                 }
                 "ucinewgame" => {
                     self.board.clear();
+                    self.controller.bot_mut().new_game();
                 }
                 "position" => {
                     return self.parse_position(&mut args);
                 }
 
                 "setoption" => {
-                    if args.next() != Some("name") {
-                        return Err(UciParseError::InvalidSyntax);
-                    }
-
-                    let option_id = args.next();
-                    if option_id.is_none() {
-                        return Err(UciParseError::InvalidSyntax);
-                    }
-                    if args.next() != Some("value") {
-                        return Ok(());
-                    }
-                    if args.peek().is_none() {
-                        return Err(UciParseError::InvalidSyntax);
-                    }
-
-                    match option_id.unwrap() {
-                        "movetime" => {
-                            let arg = str::parse::<u64>(args.next().unwrap());
-                            if let Ok(arg) = arg {
-                                if arg == 0 {
-                                    self.option_movetime = None;
-                                } else {
-                                    self.option_movetime = Some(Duration::from_millis(arg));
-                                }
-                                return Ok(());
-                            }
-                            return Err(UciParseError::InvalidSyntax);
-                        }
-
-                        _ => {
-                            return Ok(());
-                        }
-                    }
+                    return self.parse_setoption(&mut args);
                 }
                 "go" => {
-                    // Prepare to start search
-                    use std::sync::atomic::Ordering;
-                    self.stop_search.store(false, Ordering::Relaxed);
-                    let board_copy = self.board.clone();
-                    let cancelled_copy = Arc::clone(&self.stop_search);
-
-                    if let Some(time) = self.option_movetime {
-                        let _ = std::thread::spawn(move || {
-                            let mut bot = GiffiBot::new(board_copy, cancelled_copy);
-                            bot.go_timed(time);
-                        });
-                        return Ok(());
-                    }
-
-                    // TODO: REDO THIS
-                    while args.peek().is_some() {
-                        let argument = args.next().unwrap();
+                    let mut limits = SearchLimits::default();
 
+                    while let Some(argument) = args.next() {
                         match argument {
                             "wtime" | "btime" | "winc" | "binc" | "movestogo" => {
-                                if args.next().is_none() {
-                                    return Err(UciParseError::InvalidSyntax);
-                                }
+                                Self::parse_next::<i64>(&mut args, argument)?;
                             }
-
                             "movetime" => {
-                                if args.peek().is_none() {
-                                    return Err(UciParseError::InvalidSyntax);
-                                }
-
-                                let time = args.next().unwrap().parse::<u64>();
-                                if time.is_err() {
-                                    return Err(UciParseError::InvalidSyntax);
-                                }
-
-                                let search_time = Duration::from_millis(time.unwrap());
-                                let _ = std::thread::spawn(move || {
-                                    let mut bot = GiffiBot::new(board_copy, cancelled_copy);
-                                    bot.go_timed(search_time);
-                                });
-
-                                return Ok(());
+                                let time = Self::parse_next::<u64>(&mut args, argument)?;
+                                limits.movetime = Some(Duration::from_millis(time));
                             }
-
                             "depth" => {
-                                if args.peek().is_none() {
-                                    return Err(UciParseError::InvalidSyntax);
-                                }
-
-                                let depth = args.next().unwrap().parse::<i32>();
-                                if depth.is_err() {
-                                    return Err(UciParseError::InvalidSyntax);
-                                }
-
-                                let search_depth = depth.unwrap();
-                                let _ = std::thread::spawn(move || {
-                                    let mut bot = GiffiBot::new(board_copy, cancelled_copy);
-                                    bot.go_depth(search_depth);
-                                });
-
-                                return Ok(());
+                                limits.depth = Some(Self::parse_next::<i32>(&mut args, argument)?);
+                            }
+                            "nodes" => {
+                                limits.nodes = Some(Self::parse_next::<u64>(&mut args, argument)?);
+                            }
+                            "mate" => {
+                                limits.mate = Some(Self::parse_next::<i32>(&mut args, argument)?);
                             }
-
                             "infinite" => {
-                                let _ = std::thread::spawn(move || {
-                                    let mut bot = GiffiBot::new(board_copy, cancelled_copy);
-                                    bot.go_infinite();
-                                });
-                                return Ok(());
+                                limits.infinite = true;
                             }
-
-                            "perft" => {
-                                if args.peek().is_none() {
-                                    return Err(UciParseError::InvalidSyntax);
+                            "searchmoves" => {
+                                while let Some(uci_move) = args.peek() {
+                                    if GO_ARGUMENTS.contains(uci_move) {
+                                        break;
+                                    }
+                                    if uci_move.is_empty() {
+                                        args.next();
+                                        continue;
+                                    }
+                                    match Self::find_legal_move(&mut self.board, uci_move) {
+                                        Some(chess_move) => limits.searchmoves.push(chess_move),
+                                        None => {
+                                            return Err(UciParseError::IllegalMove {
+                                                index: limits.searchmoves.len() + 1,
+                                                chess_move: uci_move.to_string(),
+                                            });
+                                        }
+                                    }
+                                    args.next();
                                 }
-
-                                let depth = args.next().unwrap().parse::<u32>();
-                                if depth.is_err() {
-                                    return Err(UciParseError::InvalidSyntax);
+                                if limits.searchmoves.is_empty() {
+                                    return Err(UciParseError::MissingArgument(
+                                        argument.to_string(),
+                                    ));
                                 }
-
-                                let search_depth = depth.unwrap();
-                                self.board.perft(search_depth, true);
+                            }
+                            "perft" => {
+                                let depth = Self::parse_next::<u32>(&mut args, argument)?;
+                                perft::print_divide(&mut self.board, depth);
                                 return Ok(());
                             }
-
+                            "" => {}
                             _ => {
-                                println!("UCI: unsupported argument '{}'", argument);
+                                println!("info string unsupported argument '{}'", argument);
                             }
                         }
                     }
 
+                    if limits.movetime.is_none() {
+                        limits.movetime = self.option_movetime;
+                    }
                     const NON_ARG_THINK_TIME: Duration = Duration::from_millis(500);
-                    let _ = std::thread::spawn(move || {
-                        let mut bot = GiffiBot::new(board_copy, cancelled_copy);
-                        bot.go_timed(NON_ARG_THINK_TIME);
-                    });
+                    if limits.is_unbounded() && !limits.infinite {
+                        limits.movetime = Some(NON_ARG_THINK_TIME);
+                    }
+
+                    let skill = if self.option_limit_strength {
+                        Skill::from_elo(self.option_elo)
+                    } else {
+                        Skill::from_level(self.option_skill_level)
+                    };
+                    let bot = self.controller.bot_mut();
+                    bot.set_skill(skill);
+                    bot.set_contempt(self.option_contempt);
+                    bot.set_currmove_delay(self.option_currmove_delay);
+                    bot.set_internal_iteration(self.option_internal_iteration);
+
+                    let reporter = UciReporter::new();
+                    self.controller.start(self.board.clone(), limits, reporter);
                     return Ok(());
                 }
                 "stop" => {
-                    use std::sync::atomic::Ordering;
-                    self.stop_search.store(true, Ordering::Relaxed);
+                    self.controller.stop();
                 }
+                "quit" => {
+                    self.quit();
+                }
+                "" => {}
                 _ => {
-                    return Err(UciParseError::InvalidSyntax);
+                    return Err(UciParseError::UnknownCommand(cmd.to_string()));
                 }
             }
         }
         Ok(())
     }
 
-    fn parse_position(
-        &mut self,
-        arg_iter: &mut std::iter::Peekable<std::vec::IntoIter<&str>>,
-    ) -> Result<(), UciParseError> {
-        // startpos or fen
-        if let Some(arg1) = arg_iter.next() {
-            match arg1 {
-                "startpos" => {
-                    self.board.parse_fen(STARTPOS_FEN).expect("valid fen");
+    /// Stops the search, it's still allowed to report its move.
+    pub fn quit(&mut self) {
+        self.controller.stop();
+    }
+
+    /// Blocks until the current search has reported its move.
+    pub fn wait(&mut self) {
+        self.controller.wait();
+    }
+
+    /// The legal move in the current position matching `uci_move`
+    fn find_legal_move(board: &mut ChessBoard, uci_move: &str) -> Option<Move> {
+        board
+            .get_legal_moves()
+            .iter()
+            .copied()
+            .find(|m| m.to_uci() == uci_move)
+    }
+
+    fn parse_next<T: std::str::FromStr>(
+        arg_iter: &mut ArgIter,
+        name: &str,
+    ) -> Result<T, UciParseError> {
+        match arg_iter.next() {
+            Some(arg) if !arg.is_empty() => {
+                arg.parse::<T>()
+                    .map_err(|_| UciParseError::InvalidArgument {
+                        name: name.to_string(),
+                        value: arg.to_string(),
+                    })
+            }
+            _ => Err(UciParseError::MissingArgument(name.to_string())),
+        }
+    }
+
+    // setoption name <id> [value <x>], both may contain spaces
+    fn parse_setoption(&mut self, arg_iter: &mut ArgIter) -> Result<(), UciParseError> {
+        if arg_iter.next() != Some("name") {
+            return Err(UciParseError::MissingArgument(String::from("name")));
+        }
+
+        let mut name = vec![];
+        for arg in arg_iter.by_ref() {
+            if arg == "value" {
+                break;
+            }
+            name.push(arg);
+        }
+        let name = name.join(" ");
+        let value = arg_iter.collect::<Vec<&str>>().join(" ");
+        if name.is_empty() {
+            return Err(UciParseError::MissingArgument(String::from("name")));
+        }
+
+        let invalid_value = || UciParseError::InvalidOptionValue {
+            name: name.clone(),
+            value: value.clone(),
+        };
+
+        match name.as_str() {
+            "movetime" => {
+                let time = value.parse::<u64>().map_err(|_| invalid_value())?;
+                if time == 0 {
+                    self.option_movetime = None;
+                } else {
+                    self.option_movetime = Some(Duration::from_millis(time));
                 }
-                "fen" => {
-                    let mut whole_fen = String::from("");
+            }
+            "StrictPosition" => {
+                self.option_strict_position =
+                    Self::parse_check(&value).ok_or_else(invalid_value)?;
+            }
+            // BitsChess only castles from the standard squares, Chess960 can't be played.
+            // The option isn't offered, GUIs sending it anyway can only turn it off
+            "UCI_Chess960" => {
+                if Self::parse_check(&value).ok_or_else(invalid_value)? {
+                    return Err(invalid_value());
+                }
+            }
+            "UCI_LimitStrength" => {
+                self.option_limit_strength = Self::parse_check(&value).ok_or_else(invalid_value)?;
+            }
+            "UCI_Elo" => {
+                let elo = value.parse::<i32>().map_err(|_| invalid_value())?;
+                if !(skill::MIN_ELO..=skill::MAX_ELO).contains(&elo) {
+                    return Err(invalid_value());
+                }
+                self.option_elo = elo;
+            }
+            "Contempt" => {
+                let contempt = value.parse::<i32>().map_err(|_| invalid_value())?;
+                if !(-MAX_CONTEMPT..=MAX_CONTEMPT).contains(&contempt) {
+                    return Err(invalid_value());
+                }
+                self.option_contempt = contempt;
+            }
+            "CurrmoveDelay" => {
+                let delay = value.parse::<u64>().map_err(|_| invalid_value())?;
+                if delay > MAX_CURRMOVE_DELAY {
+                    return Err(invalid_value());
+                }
+                self.option_currmove_delay = Duration::from_millis(delay);
+            }
+            "InternalIteration" => {
+                self.option_internal_iteration = value.parse().map_err(|_| invalid_value())?;
+            }
+            "Skill Level" => {
+                let level = value.parse::<i32>().map_err(|_| invalid_value())?;
+                if !(0..=skill::MAX_SKILL_LEVEL).contains(&level) {
+                    return Err(invalid_value());
+                }
+                self.option_skill_level = level;
+            }
+            // GUIs send options like Hash and Threads to every engine, not supporting them is fine
+            _ => {
+                println!("info string unknown option '{}'", name);
+            }
+        }
+        Ok(())
+    }
 
-                    while let Some(fen_portion) = arg_iter.peek() {
-                        if *fen_portion == "moves" {
-                            break;
-                        }
-                        whole_fen.push_str(arg_iter.next().unwrap());
-                        whole_fen.push(' ');
-                    }
+    fn parse_check(value: &str) -> Option<bool> {
+        match value {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// The position is built on a copy, so a broken command never leaves a half parsed board behind.
+    fn parse_position(&mut self, arg_iter: &mut ArgIter) -> Result<(), UciParseError> {
+        let mut board = self.board.clone();
+
+        // startpos or fen
+        match arg_iter.next() {
+            Some("startpos") => {
+                board.parse_fen(STARTPOS_FEN).expect("valid fen");
+            }
+            Some("fen") => {
+                let mut whole_fen = String::from("");
 
-                    if let Err(error) = self.board.parse_fen(&whole_fen) {
-                        println!("FEN PARSE ERROR: {:?}", error);
-                        return Err(UciParseError::InvalidSyntax);
+                while let Some(fen_portion) = arg_iter.peek() {
+                    if *fen_portion == "moves" {
+                        break;
                     }
+                    whole_fen.push_str(arg_iter.next().unwrap());
+                    whole_fen.push(' ');
                 }
-                _ => {
-                    return Err(UciParseError::InvalidSyntax);
+
+                if let Err(error) = board.parse_fen(&whole_fen) {
+                    return Err(UciParseError::InvalidFen(format!("{:?}", error)));
                 }
             }
+            Some(arg) => {
+                return Err(UciParseError::InvalidArgument {
+                    name: String::from("position"),
+                    value: arg.to_string(),
+                });
+            }
+            None => {
+                return Err(UciParseError::MissingArgument(String::from("position")));
+            }
         }
 
-        // startpos or fen
-        if let Some(arg1) = arg_iter.next() {
-            match arg1 {
-                // position startpos moves e2e4 b7b5 d2d3 b8a6 glf3 e7e5 c2c3 d8g5 h2h4
-                "moves" => {
-                    for chessmove in arg_iter {
-                        if chessmove.is_empty() {
-                            continue;
+        let mut error = None;
+        match arg_iter.next() {
+            // position startpos moves e2e4 b7b5 d2d3 b8a6 glf3 e7e5 c2c3 d8g5 h2h4
+            Some("moves") => {
+                for (i, chessmove) in arg_iter.filter(|m| !m.is_empty()).enumerate() {
+                    match Self::find_legal_move(&mut board, chessmove) {
+                        Some(chess_move) => {
+                            board.make_move(chess_move, true);
+                        }
+                        None => {
+                            error = Some(UciParseError::IllegalMove {
+                                index: i + 1,
+                                chess_move: chessmove.to_string(),
+                            });
+                            break;
                         }
-                        let _ = self.board.make_move_uci(chessmove);
                     }
                 }
-                _ => {
-                    return Err(UciParseError::InvalidSyntax);
-                }
+            }
+            Some("") | None => {}
+            Some(arg) => {
+                return Err(UciParseError::InvalidArgument {
+                    name: String::from("position"),
+                    value: arg.to_string(),
+                });
             }
         }
 
-        Ok(())
+        match error {
+            // strict: the previous position stays
+            Some(error) if self.option_strict_position => Err(error),
+            // otherwise continue from the moves before the illegal one
+            Some(error) => {
+                self.board = board;
+                Err(error)
+            }
+            None => {
+                self.board = board;
+                Ok(())
+            }
+        }
     }
 }
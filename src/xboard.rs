@@ -0,0 +1,472 @@
+//! CECP v2 (XBoard / WinBoard protocol) front end, the counterpart of `UCIEngine`.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::chessbot::{
+    go::SearchLimits,
+    reporter::SearchReporter,
+    search_info::{Score, SearchInfo},
+};
+use crate::controller::SearchController;
+use bitschess::prelude::*;
+
+/// Think time without `st`, `level` or `time`, same as UCI `go` without arguments.
+const DEFAULT_THINK_TIME: Duration = Duration::from_millis(500);
+/// Moves to plan for when the time control has no move count.
+const DEFAULT_MOVES_LEFT: u32 = 30;
+/// Kept in reserve for the GUI and the OS.
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+/// Mate in N is sent as 100000 + N.
+const XBOARD_MATE_SCORE: i32 = 100000;
+
+type ArgIter<'a> = std::iter::Peekable<std::vec::IntoIter<&'a str>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XBoardError {
+    UnknownCommand(String),
+    IllegalMove(String),
+    /// The reason given by the FEN parser
+    InvalidFen(String),
+    InvalidArgument {
+        name: String,
+        value: String,
+    },
+    MissingArgument(String),
+}
+
+/// Written the way CECP expects errors back.
+impl std::fmt::Display for XBoardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownCommand(cmd) => write!(f, "Error (unknown command): {}", cmd),
+            Self::IllegalMove(chess_move) => write!(f, "Illegal move: {}", chess_move),
+            Self::InvalidFen(reason) => write!(f, "tellusererror Illegal position: {}", reason),
+            Self::InvalidArgument { name, value } => {
+                write!(f, "Error (invalid value '{}'): {}", value, name)
+            }
+            Self::MissingArgument(name) => write!(f, "Error (missing argument): {}", name),
+        }
+    }
+}
+
+impl std::error::Error for XBoardError {}
+
+/// The engine's move as reported by the search thread, applied to the board on the next command.
+#[derive(Debug, Default)]
+struct PendingMove {
+    /// Set by `force`, `result` and friends, the search still stops but its move isn't played
+    abandoned: bool,
+    best_move: Option<Move>,
+}
+
+/// Prints thinking output and `move` lines in CECP format.
+#[derive(Debug)]
+struct XBoardReporter {
+    post: bool,
+    analyzing: bool,
+    pending: Arc<Mutex<PendingMove>>,
+}
+
+impl SearchReporter for XBoardReporter {
+    fn info(&mut self, info: &SearchInfo) {
+        if !self.post {
+            return;
+        }
+        // ply score time nodes pv, score from the engine's side and time in centiseconds
+        let score = match info.score {
+            Score::Centipawns(cp) => cp,
+            Score::Mate(mate_in) if mate_in > 0 => XBOARD_MATE_SCORE + mate_in,
+            Score::Mate(mate_in) => -XBOARD_MATE_SCORE + mate_in,
+        };
+        let pv = info
+            .pv
+            .iter()
+            .map(|m| m.to_uci())
+            .collect::<Vec<String>>()
+            .join(" ");
+        println!(
+            "{} {} {} {} {}",
+            info.depth,
+            score,
+            info.time.as_millis() / 10,
+            info.nodes,
+            pv
+        );
+    }
+
+    fn bestmove(&mut self, best_move: Option<Move>) {
+        if self.analyzing {
+            return;
+        }
+        let mut pending = self.pending.lock().expect("not poisoned");
+        if pending.abandoned {
+            return;
+        }
+        if let Some(chess_move) = best_move {
+            pending.best_move = Some(chess_move);
+            println!("move {}", chess_move.to_uci());
+        }
+    }
+
+    fn message(&mut self, message: &str) {
+        println!("# {}", message);
+    }
+}
+
+pub struct XBoardEngine {
+    pub board: ChessBoard,
+    controller: SearchController,
+    pending: Arc<Mutex<PendingMove>>,
+
+    /// The side the engine plays, `None` in force mode
+    engine_white: Option<bool>,
+    post: bool,
+    analyzing: bool,
+
+    /// `sd`
+    depth_limit: Option<i32>,
+    /// `st`, exact time per move
+    fixed_movetime: Option<Duration>,
+    /// `level`, 0 moves means the base time is for the whole game
+    moves_per_session: u32,
+    increment: Duration,
+    /// `time`, the engine's remaining time
+    engine_clock: Option<Duration>,
+}
+
+impl XBoardEngine {
+    pub fn new() -> Self {
+        let mut board = ChessBoard::new();
+        board.parse_fen(STARTPOS_FEN).expect("valid fen");
+        Self {
+            board,
+            controller: SearchController::new(),
+            pending: Arc::new(Mutex::new(PendingMove::default())),
+
+            engine_white: Some(false),
+            post: false,
+            analyzing: false,
+
+            depth_limit: None,
+            fixed_movetime: None,
+            moves_per_session: 0,
+            increment: Duration::ZERO,
+            engine_clock: None,
+        }
+    }
+
+    pub fn execute_cmd(&mut self, message: &str) -> Result<(), XBoardError> {
+        self.apply_engine_move();
+
+        let mut args = message
+            .split(' ')
+            .collect::<Vec<&str>>()
+            .into_iter()
+            .peekable();
+
+        if let Some(cmd) = args.next() {
+            match cmd {
+                "xboard" | "accepted" | "rejected" | "random" | "easy" | "hard" | "computer"
+                | "name" | "rating" | "ics" | "hint" | "bk" | "draw" | "." | "" => {}
+                "protover" => {
+                    println!(
+                        "feature myname=\"GiffiBot\" setboard=1 usermove=1 ping=1 playother=1 analyze=1 colors=0 sigint=0 sigterm=0 reuse=1 done=1"
+                    );
+                }
+                "ping" => {
+                    println!("pong {}", args.collect::<Vec<&str>>().join(" "));
+                }
+                "new" => {
+                    self.abandon_search();
+                    self.board.parse_fen(STARTPOS_FEN).expect("valid fen");
+                    self.controller.bot_mut().new_game();
+                    self.engine_white = Some(false);
+                    self.depth_limit = None;
+                    self.engine_clock = None;
+                    self.restart_analysis();
+                }
+                "setboard" => {
+                    let fen = args.collect::<Vec<&str>>().join(" ");
+                    let mut board = self.board.clone();
+                    if let Err(error) = board.parse_fen(&fen) {
+                        return Err(XBoardError::InvalidFen(format!("{:?}", error)));
+                    }
+                    self.abandon_search();
+                    self.board = board;
+                    self.restart_analysis();
+                }
+                "usermove" => {
+                    let uci_move = args.next().unwrap_or("");
+                    if uci_move.is_empty() {
+                        return Err(XBoardError::MissingArgument(cmd.to_string()));
+                    }
+                    return self.user_move(uci_move);
+                }
+                "go" => {
+                    self.abandon_search();
+                    self.engine_white = Some(self.white_to_move());
+                    self.think();
+                }
+                "playother" => {
+                    self.abandon_search();
+                    self.engine_white = Some(!self.white_to_move());
+                }
+                "force" | "result" => {
+                    self.abandon_search();
+                    self.engine_white = None;
+                }
+                "?" => {
+                    // move now, the search reports its best move so far
+                    self.controller.stop();
+                }
+                "undo" => {
+                    self.take_back(1);
+                }
+                "remove" => {
+                    self.take_back(2);
+                }
+                "level" => {
+                    return self.parse_level(&mut args);
+                }
+                "st" => {
+                    self.fixed_movetime = Some(Self::parse_seconds(&mut args, cmd)?);
+                }
+                "sd" => {
+                    self.depth_limit = Some(Self::parse_next::<i32>(&mut args, cmd)?);
+                }
+                "time" => {
+                    let centis = Self::parse_next::<i64>(&mut args, cmd)?;
+                    self.engine_clock = Some(Duration::from_millis(centis.max(0) as u64 * 10));
+                }
+                "otim" => {
+                    // only the engine's own clock matters for its think time
+                    Self::parse_next::<i64>(&mut args, cmd)?;
+                }
+                "post" => {
+                    self.post = true;
+                }
+                "nopost" => {
+                    self.post = false;
+                }
+                "analyze" => {
+                    self.abandon_search();
+                    self.engine_white = None;
+                    self.analyzing = true;
+                    self.restart_analysis();
+                }
+                "exit" => {
+                    self.analyzing = false;
+                    self.abandon_search();
+                }
+                "quit" => {
+                    self.quit();
+                }
+
+                // Dev commands
+                "fen" => {
+                    println!("# FEN {}", self.board.to_fen());
+                }
+                "board" | "d" => {
+                    println!("{}", self.board);
+                }
+                _ => {
+                    return Err(XBoardError::UnknownCommand(message.to_string()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Stops the search without playing its move.
+    pub fn quit(&mut self) {
+        self.abandon_search();
+    }
+
+    /// Blocks until the current search has played its move.
+    pub fn wait(&mut self) {
+        self.controller.wait();
+        self.apply_engine_move();
+    }
+
+    fn white_to_move(&self) -> bool {
+        self.board.get_turn() == PieceColor::White
+    }
+
+    fn apply_engine_move(&mut self) {
+        let mut pending = self.pending.lock().expect("not poisoned");
+        if let Some(chess_move) = pending.best_move.take() {
+            self.board.make_move(chess_move, true);
+        }
+    }
+
+    /// Stops the search, a move it already sent is still played on the board.
+    fn abandon_search(&mut self) {
+        self.pending.lock().expect("not poisoned").abandoned = true;
+        self.controller.stop();
+        self.apply_engine_move();
+    }
+
+    fn user_move(&mut self, uci_move: &str) -> Result<(), XBoardError> {
+        let chess_move = self
+            .board
+            .get_legal_moves()
+            .iter()
+            .copied()
+            .find(|m| m.to_uci() == uci_move)
+            .ok_or_else(|| XBoardError::IllegalMove(uci_move.to_string()))?;
+
+        self.abandon_search();
+        self.board.make_move(chess_move, true);
+
+        if self.analyzing {
+            self.restart_analysis();
+        } else if self.engine_white == Some(self.white_to_move()) {
+            self.think();
+        }
+        Ok(())
+    }
+
+    fn take_back(&mut self, count: usize) {
+        self.abandon_search();
+        for _ in 0..count {
+            let _ = self.board.unmake_move();
+        }
+        self.restart_analysis();
+    }
+
+    /// Starts searching for the engine's move, or announces the result if the game is over.
+    fn think(&mut self) {
+        if self.board.get_legal_moves().is_empty() {
+            let white_to_move = self.white_to_move();
+            let turn = self.board.get_turn();
+            if !self.board.is_king_in_check(turn) {
+                println!("1/2-1/2 {{Stalemate}}");
+            } else if white_to_move {
+                println!("0-1 {{Black mates}}");
+            } else {
+                println!("1-0 {{White mates}}");
+            }
+            return;
+        }
+
+        let mut limits = SearchLimits {
+            depth: self.depth_limit,
+            movetime: self.think_time(),
+            ..Default::default()
+        };
+        if limits.is_unbounded() {
+            limits.movetime = Some(DEFAULT_THINK_TIME);
+        }
+        self.start_search(limits, false);
+    }
+
+    fn restart_analysis(&mut self) {
+        if self.analyzing {
+            self.start_search(SearchLimits::infinite(), true);
+        }
+    }
+
+    fn start_search(&mut self, limits: SearchLimits, analyzing: bool) {
+        *self.pending.lock().expect("not poisoned") = PendingMove::default();
+        let reporter = XBoardReporter {
+            // analysis output is the whole point of analyze mode
+            post: self.post || analyzing,
+            analyzing,
+            pending: Arc::clone(&self.pending),
+        };
+        self.controller.start(self.board.clone(), limits, reporter);
+    }
+
+    /// Time for the next move from `st`, or the clock split over the moves left in the session.
+    fn think_time(&self) -> Option<Duration> {
+        if let Some(time) = self.fixed_movetime {
+            return Some(time);
+        }
+        let clock = self.engine_clock?;
+
+        let moves_left = if self.moves_per_session > 0 {
+            let fullmove = self
+                .board
+                .to_fen()
+                .split_whitespace()
+                .last()
+                .and_then(|n| n.parse::<u32>().ok())
+                .unwrap_or(1);
+            self.moves_per_session - (fullmove.max(1) - 1) % self.moves_per_session
+        } else {
+            DEFAULT_MOVES_LEFT
+        };
+
+        let time = clock / moves_left + self.increment * 3 / 4;
+        Some(time.min(clock.saturating_sub(MOVE_OVERHEAD) / 2))
+    }
+
+    // level MPS BASE INC, BASE is minutes or minutes:seconds and INC seconds
+    fn parse_level(&mut self, arg_iter: &mut ArgIter) -> Result<(), XBoardError> {
+        let moves_per_session = Self::parse_next::<u32>(arg_iter, "level")?;
+
+        let base = arg_iter
+            .next()
+            .filter(|arg| !arg.is_empty())
+            .ok_or_else(|| XBoardError::MissingArgument(String::from("level")))?;
+        let invalid_base = || XBoardError::InvalidArgument {
+            name: String::from("level"),
+            value: base.to_string(),
+        };
+        let base = match base.split_once(':') {
+            Some((minutes, seconds)) => {
+                let minutes = minutes.parse::<u64>().map_err(|_| invalid_base())?;
+                let seconds = seconds.parse::<u64>().map_err(|_| invalid_base())?;
+                Duration::from_secs(minutes * 60 + seconds)
+            }
+            None => Duration::from_secs(base.parse::<u64>().map_err(|_| invalid_base())? * 60),
+        };
+
+        let increment = Self::parse_seconds(arg_iter, "level")?;
+
+        self.moves_per_session = moves_per_session;
+        self.increment = increment;
+        self.fixed_movetime = None;
+        // the GUI sends 'time' before every move, until then the whole base time is left
+        self.engine_clock = Some(base);
+        Ok(())
+    }
+
+    /// Fractional seconds, negative values count as zero. `inf`, `NaN` and values too large
+    /// for a `Duration` are rejected.
+    fn parse_seconds(arg_iter: &mut ArgIter, name: &str) -> Result<Duration, XBoardError> {
+        let arg = arg_iter.peek().copied().unwrap_or_default();
+        let seconds = Self::parse_next::<f64>(arg_iter, name)?;
+        let invalid = || XBoardError::InvalidArgument {
+            name: name.to_string(),
+            value: arg.to_string(),
+        };
+        // NaN.max(0.0) is 0.0, check before clamping
+        if !seconds.is_finite() {
+            return Err(invalid());
+        }
+        Duration::try_from_secs_f64(seconds.max(0.0)).map_err(|_| invalid())
+    }
+
+    fn parse_next<T: std::str::FromStr>(
+        arg_iter: &mut ArgIter,
+        name: &str,
+    ) -> Result<T, XBoardError> {
+        match arg_iter.next() {
+            Some(arg) if !arg.is_empty() => {
+                arg.parse::<T>().map_err(|_| XBoardError::InvalidArgument {
+                    name: name.to_string(),
+                    value: arg.to_string(),
+                })
+            }
+            _ => Err(XBoardError::MissingArgument(name.to_string())),
+        }
+    }
+}
+
+impl Default for XBoardEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -29,11 +29,12 @@ import time
 
 INFO_RE = re.compile(
     r"info depth (?P<depth>\d+) "
+    r"seldepth (?P<seldepth>\d+) "
     r"score (?P<score>cp -?\d+|mate -?\d+) "
-    r"currmove (?P<currmove>\S+) "
     r"nodes (?P<nodes>\d+) "
     r"time (?P<time>[\d.]+) "
     r"nps (?P<nps>\d+) "
+    r"hashfull \d+ "
     r"pv (?P<pv>.*)"
 )
 
@@ -106,7 +107,7 @@ def parse_info(line):
         "depth": int(m.group("depth")),
         "score": score,
         "is_mate": is_mate,
-        "currmove": m.group("currmove"),
+        "seldepth": int(m.group("seldepth")),
         "nodes": int(m.group("nodes")),
         "time": float(m.group("time")) / 100.0,
         "nps": int(m.group("nps")),
@@ -127,7 +128,7 @@ def send_position(reader, fen):
         line = reader.read_line()
         if line is None:
             return False, "timeout"
-        if "FEN PARSE ERROR" in line:
+        if line.startswith("info string invalid FEN"):
             error = "fen-parse-error"
             continue
         if line.strip() == "readyok":
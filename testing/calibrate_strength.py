@@ -0,0 +1,98 @@
+#!/usr/bin/env python3
+"""Estimate the playing strength of GiffiBot's UCI_Elo / Skill Level settings.
+
+Plays a match between GiffiBot at a given setting and Stockfish limited to a
+known UCI_Elo, then converts the score into an Elo estimate:
+
+    elo = opponent_elo - 400 * log10(1 / score - 1)
+
+Starting positions come from positions.txt and every position is played with
+both colors. Needs python-chess: pip install chess
+
+Usage:
+    python3 testing/calibrate_strength.py --engine target/release/giffibot \\
+        --stockfish /usr/bin/stockfish --elo 1200 --opponent-elo 1350 --games 40
+    python3 testing/calibrate_strength.py --skill-level 5 --opponent-elo 1350
+"""
+
+import argparse
+import math
+import os
+import sys
+
+import chess
+import chess.engine
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+
+
+def load_positions(path, count):
+    with open(path) as f:
+        fens = [line.strip() for line in f if line.strip()]
+    return fens[:count]
+
+
+def play_game(white, black, fen, movetime):
+    board = chess.Board(fen)
+    limit = chess.engine.Limit(time=movetime / 1000)
+    while not board.is_game_over(claim_draw=True):
+        engine = white if board.turn == chess.WHITE else black
+        result = engine.play(board, limit)
+        if result.move is None:
+            break
+        board.push(result.move)
+    return board.result(claim_draw=True)
+
+
+def elo_from_score(score, opponent_elo):
+    # a perfect or zero score has no finite estimate, clamp it
+    score = min(max(score, 0.01), 0.99)
+    return opponent_elo - 400 * math.log10(1 / score - 1)
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__, formatter_class=argparse.RawDescriptionHelpFormatter)
+    parser.add_argument("--engine", default="target/release/giffibot")
+    parser.add_argument("--stockfish", default="stockfish")
+    setting = parser.add_mutually_exclusive_group()
+    setting.add_argument("--elo", type=int, help="GiffiBot UCI_Elo (enables UCI_LimitStrength)")
+    setting.add_argument("--skill-level", type=int, help="GiffiBot Skill Level")
+    parser.add_argument("--opponent-elo", type=int, default=1350, help="Stockfish UCI_Elo (min 1320)")
+    parser.add_argument("--games", type=int, default=40, help="games, rounded up to an even number")
+    parser.add_argument("--movetime", type=int, default=200, help="ms per move for both engines")
+    parser.add_argument("--positions", default=os.path.join(HERE, "positions.txt"))
+    args = parser.parse_args()
+
+    bot = chess.engine.SimpleEngine.popen_uci(args.engine)
+    stockfish = chess.engine.SimpleEngine.popen_uci(args.stockfish)
+    try:
+        if args.elo is not None:
+            bot.configure({"UCI_LimitStrength": True, "UCI_Elo": args.elo})
+        elif args.skill_level is not None:
+            bot.configure({"Skill Level": args.skill_level})
+        stockfish.configure({"UCI_LimitStrength": True, "UCI_Elo": args.opponent_elo})
+
+        pairs = (args.games + 1) // 2
+        points = 0.0
+        played = 0
+        for i, fen in enumerate(load_positions(args.positions, pairs)):
+            for bot_white in (True, False):
+                white, black = (bot, stockfish) if bot_white else (stockfish, bot)
+                result = play_game(white, black, fen, args.movetime)
+                if result == "1/2-1/2":
+                    points += 0.5
+                elif (result == "1-0") == bot_white and result != "*":
+                    points += 1.0
+                played += 1
+                print(f"game {played}: {result} (GiffiBot {'white' if bot_white else 'black'})", file=sys.stderr)
+
+        score = points / played
+        print(f"score {points}/{played} ({score:.1%}) vs Stockfish UCI_Elo {args.opponent_elo}")
+        print(f"estimated Elo {elo_from_score(score, args.opponent_elo):.0f}")
+    finally:
+        bot.quit()
+        stockfish.quit()
+
+
+if __name__ == "__main__":
+    main()
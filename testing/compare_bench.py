@@ -0,0 +1,135 @@
+#!/usr/bin/env python3
+"""Compare the bench summary of GiffiBot between git revisions.
+
+Every revision is checked out in a temporary worktree, built in release mode and
+run with `giffibot bench <depth>`. The node count, time, transposition table
+entries per MB and TT hit rate of each build are printed side by side, so a
+change to the search or the table can be measured against the commit before it.
+
+With --modes every build is benched once per internal iteration mode (`off`,
+`reduction`, `deepening`), each mode gets its own column.
+
+Usage:
+    python3 testing/compare_bench.py --base HEAD~1 --head HEAD --depth 8
+    python3 testing/compare_bench.py --base v1.2 --head HEAD
+    python3 testing/compare_bench.py --base HEAD --head HEAD --modes off,reduction,deepening
+"""
+
+import argparse
+import os
+import re
+import shutil
+import subprocess
+import sys
+import tempfile
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+ROOT = os.path.dirname(HERE)
+
+SUMMARY_RE = re.compile(r"^(?P<key>[^:]+?)\s*:\s*(?P<value>\S+)")
+FIELDS = (
+    ("nodes", "Nodes searched"),
+    ("time_ms", "Total time (ms)"),
+    ("nps", "Nodes/second"),
+    ("entries_per_mb", "TT entries/MB"),
+    ("hit_rate", "TT hit rate"),
+)
+
+
+def git(*args):
+    return subprocess.run(
+        ["git", *args], cwd=ROOT, check=True, capture_output=True, text=True
+    ).stdout.strip()
+
+
+def build_revision(rev, workdir):
+    """Checks `rev` out into `workdir` and builds it, returns the engine binary."""
+    git("worktree", "add", "--detach", workdir, rev)
+    print(f"building {rev} in {workdir}", file=sys.stderr)
+    subprocess.run(
+        ["cargo", "build", "--release", "--quiet"], cwd=workdir, check=True
+    )
+    return os.path.join(workdir, "target", "release", "giffibot")
+
+
+def run_bench(engine, depth, mode=None):
+    command = [engine, "bench", str(depth)]
+    if mode is not None:
+        command.append(mode)
+    out = subprocess.run(command, check=True, capture_output=True, text=True).stdout
+    summary = {}
+    for line in out.splitlines():
+        m = SUMMARY_RE.match(line.strip())
+        if m:
+            summary[m.group("key")] = m.group("value").rstrip("%")
+    result = {}
+    for field, label in FIELDS:
+        if label in summary:
+            result[field] = float(summary[label])
+    return result
+
+
+def print_table(rows):
+    labels = [label for _, label in FIELDS]
+    width = max(len(label) for label in labels)
+    revs = [rev for rev, _ in rows]
+    col = max(12, *(len(rev) for rev in revs))
+    print(" " * width + "".join(f"  {rev:>{col}}" for rev in revs))
+    for field, label in FIELDS:
+        cells = []
+        for _, result in rows:
+            value = result.get(field)
+            if value is None:
+                cells.append(f"  {'-':>{col}}")
+            elif field == "hit_rate":
+                cells.append(f"  {value:>{col - 1}.1f}%")
+            else:
+                cells.append(f"  {value:>{col}.0f}")
+        print(f"{label:<{width}}" + "".join(cells))
+
+
+def main():
+    ap = argparse.ArgumentParser(
+        description=__doc__, formatter_class=argparse.RawDescriptionHelpFormatter
+    )
+    ap.add_argument("--base", default="HEAD~1", help="revision to compare against")
+    ap.add_argument("--head", default="HEAD", help="revision with the change")
+    ap.add_argument("--depth", type=int, default=6)
+    ap.add_argument(
+        "--modes",
+        default=None,
+        help="comma separated internal iteration modes to bench, e.g. off,reduction,deepening",
+    )
+    args = ap.parse_args()
+    if args.depth < 1:
+        print("error: --depth must be >= 1", file=sys.stderr)
+        sys.exit(1)
+
+    modes = args.modes.split(",") if args.modes else [None]
+    revisions = [args.base] if args.base == args.head else [args.base, args.head]
+
+    tmp = tempfile.mkdtemp(prefix="giffibot-bench-")
+    worktrees = []
+    rows = []
+    try:
+        for rev in revisions:
+            workdir = os.path.join(tmp, git("rev-parse", "--short", rev))
+            engine = build_revision(rev, workdir)
+            worktrees.append(workdir)
+            for mode in modes:
+                label = rev if mode is None else f"{rev}:{mode}"
+                rows.append((label, run_bench(engine, args.depth, mode)))
+    finally:
+        for workdir in worktrees:
+            git("worktree", "remove", "--force", workdir)
+        shutil.rmtree(tmp, ignore_errors=True)
+
+    print(f"# bench depth={args.depth}")
+    print_table(rows)
+
+
+if __name__ == "__main__":
+    try:
+        main()
+    except KeyboardInterrupt:
+        sys.exit(130)
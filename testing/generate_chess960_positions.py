@@ -0,0 +1,85 @@
+#!/usr/bin/env python3
+"""Generate Chess960 (Fischer Random) start positions for self-test matches.
+
+Positions are numbered 0-959 with Scharnagl's scheme (518 is the standard
+start position) and written in the same "fen;" per line format as
+positions.txt, so they can be handed to c-chess-cli like the others.
+
+Positions are written without castling rights by default. BitsChess only
+castles with the king on the e-file and the castling rook on the a- or h-file,
+so GiffiBot doesn't offer UCI_Chess960 and plays these positions as normal
+chess, without castling.
+
+--castling shredder writes the rights in Shredder-FEN ("HAha") for engines that
+castle in every setup. GiffiBot can't play those FENs.
+
+Usage:
+    python3 testing/generate_chess960_positions.py --count 100 --output positions960.txt
+    python3 testing/generate_chess960_positions.py --all
+"""
+
+import argparse
+import random
+
+# Knight, rook and king placement on the 5 squares left after bishops and queen
+KRN_TABLE = [
+    "NNRKR", "NRNKR", "NRKNR", "NRKRN", "RNNKR",
+    "RNKNR", "RNKRN", "RKNNR", "RKNRN", "RKRNN",
+]
+
+
+def back_rank(number):
+    """White's back rank (a-h) for the Scharnagl number 0-959."""
+    rank = [None] * 8
+
+    number, light_bishop = divmod(number, 4)
+    rank[light_bishop * 2 + 1] = "B"
+    number, dark_bishop = divmod(number, 4)
+    rank[dark_bishop * 2] = "B"
+
+    number, queen = divmod(number, 6)
+    empty = [i for i, piece in enumerate(rank) if piece is None]
+    rank[empty[queen]] = "Q"
+
+    empty = [i for i, piece in enumerate(rank) if piece is None]
+    for square, piece in zip(empty, KRN_TABLE[number]):
+        rank[square] = piece
+    return "".join(rank)
+
+
+def to_fen(number, castling):
+    white = back_rank(number)
+    black = white.lower()
+
+    rights = "-"
+    if castling == "shredder":
+        rook_files = [chr(ord("a") + i) for i, piece in enumerate(white) if piece == "R"]
+        # outer rooks, king side first
+        rights = rook_files[1].upper() + rook_files[0].upper() + rook_files[1] + rook_files[0]
+
+    return f"{black}/pppppppp/8/8/8/8/PPPPPPPP/{white} w {rights} - 0 1"
+
+
+def main():
+    ap = argparse.ArgumentParser(description="Generate Chess960 start positions.")
+    ap.add_argument("--count", type=int, default=100, help="random positions to generate")
+    ap.add_argument("--all", action="store_true", help="write all 960 positions in order")
+    ap.add_argument("--seed", type=int, default=None)
+    ap.add_argument("--castling", choices=("none", "shredder"), default="none")
+    ap.add_argument("--output", default="./positions960.txt")
+    args = ap.parse_args()
+
+    if args.all:
+        numbers = list(range(960))
+    else:
+        rng = random.Random(args.seed)
+        numbers = rng.sample(range(960), min(args.count, 960))
+
+    with open(args.output, "w", encoding="utf-8") as output:
+        for number in numbers:
+            output.write(to_fen(number, args.castling) + ";\n")
+    print(f"wrote {len(numbers)} positions to {args.output}")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,29 @@
+use giffibot::*;
+
+#[test]
+fn test_bench_is_deterministic() {
+    let first = bench(3);
+    let second = bench(3);
+    assert_eq!(first.nodes_per_position, second.nodes_per_position);
+    assert_eq!(first.nodes, first.nodes_per_position.iter().sum::<u64>());
+    assert!(first.nodes > 0);
+}
+
+#[test]
+fn test_bench_internal_iteration() {
+    // only the plumbing, internal_iteration_test1 tests what each mode does to a node
+    for internal_iteration in [
+        InternalIteration::Off,
+        InternalIteration::Reduction,
+        InternalIteration::Deepening,
+    ] {
+        let result = bench_with(3, internal_iteration);
+        assert_eq!(result.internal_iteration, internal_iteration);
+        assert!(result.nodes > 0);
+    }
+    assert_eq!(
+        "iid".parse::<InternalIteration>(),
+        Ok(InternalIteration::Deepening)
+    );
+    assert!("sometimes".parse::<InternalIteration>().is_err());
+}
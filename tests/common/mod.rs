@@ -0,0 +1,23 @@
+use std::sync::{atomic::AtomicBool, Arc};
+
+use giffibot::*;
+
+/// A silent engine on `fen`.
+pub fn engine(fen: &str) -> GiffiBot {
+    let mut board = ChessBoard::new();
+    board.parse_fen(fen).expect("Invalid FEN");
+    let mut engine = GiffiBot::new(board, Arc::new(AtomicBool::new(false)));
+    engine.set_reporter(SilentReporter);
+    engine
+}
+
+/// The legal move of the engine's position written as `uci`.
+pub fn find_move(engine: &GiffiBot, uci: &str) -> Move {
+    engine
+        .board
+        .get_legal_moves()
+        .iter()
+        .copied()
+        .find(|m| m.to_uci() == uci)
+        .expect("legal move")
+}
@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+use giffibot::*;
+
+#[test]
+fn test_one_bestmove_per_go() {
+    let mut board = ChessBoard::new();
+    board.parse_fen(STARTPOS_FEN).expect("Invalid FEN");
+
+    let reporter = CollectingReporter::new();
+    let mut controller = SearchController::new();
+
+    // the second search has to wait for the first one to report its move
+    controller.start(board.clone(), SearchLimits::infinite(), reporter.clone());
+    controller.start(board.clone(), SearchLimits::infinite(), reporter.clone());
+    std::thread::sleep(Duration::from_millis(50));
+    assert!(controller.is_searching());
+
+    controller.stop();
+    assert!(!controller.is_searching());
+    assert_eq!(reporter.best_moves().len(), 2);
+    assert!(reporter.best_moves().iter().all(|m| m.is_some()));
+
+    // stop without a search running reports nothing
+    controller.stop();
+    assert_eq!(reporter.best_moves().len(), 2);
+}
@@ -39,6 +39,95 @@ fn test_mate_in_threes() {
     assert_eq!(m2.to_uci(), "b3b6"); // 1. Qb6+ Kc8 2. Bxf5 Bc6 3. Qc7#
 }
 
+#[test]
+fn test_search_result_score() {
+    let mut board = ChessBoard::new();
+    board
+        .parse_fen("1kb5/1p1p3p/2p4p/4ppq1/5n2/Q1P5/2P2PPP/R5K1 w - - 4 29")
+        .expect("Invalid FEN");
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut engine = GiffiBot::new(board, stop);
+    let result = engine.go_depth(4);
+    assert_eq!(result.best_move.expect("?").to_uci(), "a3d6");
+    assert_eq!(result.info.score, Score::Mate(1));
+    assert_eq!(result.info.pv.first(), result.best_move.as_ref());
+}
+
+#[test]
+fn test_go_mate_limit() {
+    let mut board = ChessBoard::new();
+    board
+        .parse_fen("8/7R/2r5/8/P3n3/8/3nk1PP/R5K1 b - - 0 1")
+        .expect("Invalid FEN");
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut engine = GiffiBot::new(board, stop);
+    engine.go(&SearchLimits {
+        mate: Some(3),
+        nodes: Some(50_000_000),
+        ..Default::default()
+    });
+    assert_eq!(engine.pv.front().expect("?").to_uci(), "d2f3");
+}
+
+#[test]
+fn test_go_mate_limit_when_mated() {
+    // 1... Kh7 2. Rh1# mates the side to move, that's no reason to stop early
+    let mut board = ChessBoard::new();
+    board
+        .parse_fen("7k/5K2/8/8/8/8/8/R7 b - - 0 1")
+        .expect("Invalid FEN");
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut engine = GiffiBot::new(board, stop);
+    let result = engine.go(&SearchLimits {
+        mate: Some(3),
+        ..Default::default()
+    });
+    assert_eq!(result.info.score, Score::Mate(-1));
+    // searched up to the depth a mate in 3 needs
+    assert_eq!(result.info.depth, 5);
+}
+
+#[test]
+fn test_mate_score_through_tt() {
+    let mut board = ChessBoard::new();
+    board
+        .parse_fen("8/7R/2r5/8/P3n3/8/3nk1PP/R5K1 b - - 0 1")
+        .expect("Invalid FEN");
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut engine = GiffiBot::new(board, stop);
+    let first = engine.go_depth(7);
+    assert_eq!(first.info.score, Score::Mate(3));
+
+    // the second search is answered by the table from the first one, stored mate
+    // distances have to come back counted from the root
+    let second = engine.go_depth(7);
+    assert_eq!(second.info.score, Score::Mate(3));
+    assert_eq!(second.best_move.expect("?").to_uci(), "d2f3");
+}
+
+#[test]
+fn test_mate_in_three_with_warm_tt() {
+    // searching another position first fills the table with unrelated scores
+    let mut board = ChessBoard::new();
+    board.parse_fen(STARTPOS_FEN).expect("Invalid FEN");
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut engine = GiffiBot::new(board, stop);
+    engine.go_depth(4);
+
+    // 1. Qb6+ Kc8 2. Bxf5 Bc6 3. Qc7#
+    engine
+        .board
+        .parse_fen("rn1k3r/1b1q1ppp/p2P4/2B2p2/8/1QNBR3/PP3PPP/2R3K1 w - - 1 0")
+        .expect("Invalid FEN");
+    let result = engine.go_depth(7);
+    assert_eq!(result.best_move.expect("?").to_uci(), "b3b6");
+    assert_eq!(result.info.score, Score::Mate(3));
+}
+
 #[test]
 fn test_atleast_depth_1() {
     // The position is very complex should take more than 10ms to run.
@@ -0,0 +1,29 @@
+use giffibot::*;
+
+#[test]
+fn test_perft_divide() {
+    let mut board = ChessBoard::new();
+    board.parse_fen(STARTPOS_FEN).expect("Invalid FEN");
+    let before = board.to_fen();
+
+    let counts = divide(&mut board, 3);
+    assert_eq!(counts.len(), 20);
+    assert_eq!(counts.iter().map(|(_, nodes)| nodes).sum::<u64>(), 8902);
+    // make/unmake leaves the board as it was
+    assert_eq!(board.to_fen(), before);
+}
+
+#[test]
+fn test_perft_suite_shallow() {
+    let cases = parse_epd(BUNDLED_SUITE).expect("valid suite");
+    assert!(!cases.is_empty());
+    assert_eq!(run_suite(&cases, 3), vec![]);
+}
+
+// cargo test --release -- --ignored
+#[test]
+#[ignore]
+fn test_perft_suite_full() {
+    let cases = parse_epd(BUNDLED_SUITE).expect("valid suite");
+    assert_eq!(run_suite(&cases, u32::MAX), vec![]);
+}
@@ -0,0 +1,44 @@
+use std::sync::{atomic::AtomicBool, Arc};
+
+use giffibot::*;
+
+#[test]
+fn test_collecting_reporter() {
+    let mut board = ChessBoard::new();
+    board.parse_fen(STARTPOS_FEN).expect("Invalid FEN");
+
+    let reporter = CollectingReporter::new();
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut engine = GiffiBot::new(board, stop);
+    engine.set_reporter(reporter.clone());
+    let result = engine.go_depth(3);
+
+    let infos = reporter.infos();
+    assert_eq!(infos.len(), 3);
+    assert_eq!(infos.last().expect("?").depth, 3);
+    assert!(infos.iter().all(|info| info.seldepth >= info.depth));
+    assert_eq!(reporter.best_moves(), vec![result.best_move]);
+}
+
+#[test]
+fn test_pv_is_legal() {
+    let fen = "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP1QBPPP/R3KB1R w KQ - 2 9";
+    let mut board = ChessBoard::new();
+    board.parse_fen(fen).expect("Invalid FEN");
+
+    let reporter = CollectingReporter::new();
+    let mut engine = GiffiBot::new(board, Arc::new(AtomicBool::new(false)));
+    engine.set_reporter(reporter.clone());
+    engine.go_depth(5);
+
+    for info in reporter.infos() {
+        assert!(!info.pv.is_empty());
+
+        let mut board = ChessBoard::new();
+        board.parse_fen(fen).expect("Invalid FEN");
+        for chess_move in &info.pv {
+            assert!(board.get_legal_moves().iter().any(|m| m == chess_move));
+            board.make_move(*chess_move, true);
+        }
+    }
+}
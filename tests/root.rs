@@ -0,0 +1,57 @@
+mod common;
+
+use common::{engine, find_move};
+use giffibot::*;
+
+fn assert_sorted(root_moves: &[RootMove]) {
+    assert!(root_moves
+        .windows(2)
+        .all(|pair| pair[0].score >= pair[1].score));
+}
+
+#[test]
+fn test_root_moves() {
+    let mut engine = engine(STARTPOS_FEN);
+    let result = engine.go_depth(3);
+
+    let root_moves = engine.root_moves();
+    assert_eq!(root_moves.len(), 20);
+    assert_eq!(Some(root_moves[0].chess_move), result.best_move);
+    assert!(result.info.pv.starts_with(&root_moves[0].pv));
+    assert_sorted(root_moves);
+    assert!(root_moves.iter().all(|root_move| root_move.nodes > 0));
+    // the best move had an exact score at depth 2 too, whichever move that was
+    assert!(root_moves[0].score > -i32::MAX);
+}
+
+#[test]
+fn test_root_moves_searchmoves() {
+    let mut engine = engine(STARTPOS_FEN);
+    let allowed = vec![find_move(&engine, "a2a3"), find_move(&engine, "h2h3")];
+    let result = engine.go(&SearchLimits {
+        depth: Some(3),
+        searchmoves: allowed.clone(),
+        ..Default::default()
+    });
+
+    let root_moves = engine.root_moves();
+    assert_eq!(root_moves.len(), 2);
+    assert!(root_moves
+        .iter()
+        .all(|root_move| allowed.contains(&root_move.chess_move)));
+    assert!(allowed.contains(&result.best_move.expect("a move")));
+}
+
+#[test]
+fn test_root_moves_after_cancel() {
+    let mut engine = engine("r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP1QBPPP/R3KB1R w KQ - 2 9");
+    // stops in the middle of an iteration
+    let result = engine.go(&SearchLimits {
+        nodes: Some(20_000),
+        ..Default::default()
+    });
+
+    let root_moves = engine.root_moves();
+    assert_sorted(root_moves);
+    assert_eq!(Some(root_moves[0].chess_move), result.best_move);
+}
@@ -0,0 +1,95 @@
+use giffibot::*;
+
+fn fen_after_e4() -> String {
+    let mut engine = UCIEngine::new();
+    engine.execute_cmd("position startpos moves e2e4").unwrap();
+    engine.board.to_fen()
+}
+
+#[test]
+fn test_illegal_position_move() {
+    let mut engine = UCIEngine::new();
+    let error = engine.execute_cmd("position startpos moves e2e4 e2e4 d7d5");
+    assert_eq!(
+        error,
+        Err(UciParseError::IllegalMove {
+            index: 2,
+            chess_move: String::from("e2e4")
+        })
+    );
+    // continues from the moves before the illegal one
+    assert_eq!(engine.board.to_fen(), fen_after_e4());
+}
+
+#[test]
+fn test_strict_position() {
+    let mut engine = UCIEngine::new();
+    engine
+        .execute_cmd("setoption name StrictPosition value true")
+        .unwrap();
+    engine.execute_cmd("position startpos moves e2e4").unwrap();
+
+    assert!(engine
+        .execute_cmd("position startpos moves d2d4 d2d4")
+        .is_err());
+    assert_eq!(engine.board.to_fen(), fen_after_e4());
+
+    assert!(matches!(
+        engine.execute_cmd("position fen not a fen"),
+        Err(UciParseError::InvalidFen(_))
+    ));
+    assert_eq!(engine.board.to_fen(), fen_after_e4());
+
+    // only positions are strict, unknown options are still ignored
+    assert_eq!(engine.execute_cmd("setoption name Hash value 128"), Ok(()));
+}
+
+#[test]
+fn test_unknown_options_ignored() {
+    let mut engine = UCIEngine::new();
+    assert_eq!(engine.execute_cmd("setoption name Hash value 128"), Ok(()));
+    assert_eq!(engine.execute_cmd("setoption name Threads value 4"), Ok(()));
+}
+
+#[test]
+fn test_command_errors() {
+    let mut engine = UCIEngine::new();
+    assert_eq!(
+        engine.execute_cmd("gogo"),
+        Err(UciParseError::UnknownCommand(String::from("gogo")))
+    );
+    assert_eq!(
+        engine.execute_cmd("setoption name movetime value fast"),
+        Err(UciParseError::InvalidOptionValue {
+            name: String::from("movetime"),
+            value: String::from("fast")
+        })
+    );
+    assert_eq!(
+        engine.execute_cmd("go depth"),
+        Err(UciParseError::MissingArgument(String::from("depth")))
+    );
+}
+
+#[test]
+fn test_chess960_refused() {
+    let mut engine = UCIEngine::new();
+    assert_eq!(
+        engine.execute_cmd("setoption name UCI_Chess960 value true"),
+        Err(UciParseError::InvalidOptionValue {
+            name: String::from("UCI_Chess960"),
+            value: String::from("true")
+        })
+    );
+    assert_eq!(
+        engine.execute_cmd("setoption name UCI_Chess960 value false"),
+        Ok(())
+    );
+
+    // a 960 start position without castling rights is normal chess
+    let fen = "bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w - - 0 1";
+    assert_eq!(
+        engine.execute_cmd(&format!("position fen {} moves h1g3", fen)),
+        Ok(())
+    );
+}
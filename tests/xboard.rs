@@ -0,0 +1,70 @@
+use giffibot::*;
+
+fn fen_after_e4() -> String {
+    let mut engine = UCIEngine::new();
+    engine.execute_cmd("position startpos moves e2e4").unwrap();
+    engine.board.to_fen()
+}
+
+#[test]
+fn test_force_mode_moves() {
+    let mut engine = XBoardEngine::new();
+    engine.execute_cmd("new").unwrap();
+    engine.execute_cmd("force").unwrap();
+    engine.execute_cmd("usermove e2e4").unwrap();
+    assert_eq!(engine.board.to_fen(), fen_after_e4());
+
+    assert_eq!(
+        engine.execute_cmd("usermove e2e4"),
+        Err(XBoardError::IllegalMove(String::from("e2e4")))
+    );
+    engine.execute_cmd("undo").unwrap();
+    engine.execute_cmd("usermove e2e4").unwrap();
+    assert_eq!(engine.board.to_fen(), fen_after_e4());
+}
+
+#[test]
+fn test_engine_plays_its_move() {
+    let mut engine = XBoardEngine::new();
+    engine.execute_cmd("new").unwrap();
+    engine.execute_cmd("sd 2").unwrap();
+
+    // the engine plays black by default and answers the user's move
+    engine.execute_cmd("usermove e2e4").unwrap();
+    engine.wait();
+    assert!(engine.board.get_turn() == PieceColor::White);
+
+    // 'go' makes the engine play the side to move
+    engine.execute_cmd("force").unwrap();
+    engine.execute_cmd("go").unwrap();
+    engine.wait();
+    assert!(engine.board.get_turn() == PieceColor::Black);
+}
+
+#[test]
+fn test_xboard_command_errors() {
+    let mut engine = XBoardEngine::new();
+    assert_eq!(
+        engine.execute_cmd("gogo"),
+        Err(XBoardError::UnknownCommand(String::from("gogo")))
+    );
+    assert_eq!(
+        engine.execute_cmd("sd deep"),
+        Err(XBoardError::InvalidArgument {
+            name: String::from("sd"),
+            value: String::from("deep")
+        })
+    );
+    assert!(engine.execute_cmd("level 40 5:30 0").is_ok());
+    assert!(engine.execute_cmd("level 40 5:xx 0").is_err());
+    assert_eq!(
+        engine.execute_cmd("st inf"),
+        Err(XBoardError::InvalidArgument {
+            name: String::from("st"),
+            value: String::from("inf")
+        })
+    );
+    assert!(engine.execute_cmd("st NaN").is_err());
+    assert!(engine.execute_cmd("level 40 5 1e300").is_err());
+    assert!(engine.execute_cmd("st 2.5").is_ok());
+}